@@ -1,8 +1,10 @@
 extern crate pest;
 
+use std::env;
 use std::fs;
 use std::path::Path;
 use tsumego_solver::gotools_parser;
+use tsumego_solver::puzzle::{Engine, NoProfile, SolveLimits};
 
 use gotools_parser::PuzzleCollection;
 use std::borrow::Borrow;
@@ -19,23 +21,39 @@ fn read_puzzles() -> Result<PuzzleCollection, Box<dyn Error>> {
     Ok(puzzles)
 }
 
+/// The engine named by the first argument that isn't a flag,
+/// as cargo passes flags of its own to the bench.
+fn read_engine() -> Result<Engine, Box<dyn Error>> {
+    match env::args().skip(1).find(|arg| !arg.starts_with('-')) {
+        Some(name) => Ok(name.parse()?),
+        None => Ok(Engine::ProofNumber),
+    }
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
-    let mut puzzles = read_puzzles()?;
+    let engine = read_engine()?;
+    let puzzles = read_puzzles()?;
 
     let mut solved_count = 0;
 
-    for puzzle in puzzles.valid_puzzles.iter_mut() {
-        let solved = puzzle.solve_with_timeout(Duration::from_millis(10));
+    for puzzle in puzzles.valid_puzzles.iter() {
+        let (game, goal) = puzzle.build()?;
+
+        let mut solver = engine.solver::<NoProfile>(game, goal);
+
+        let result = solver.search(SolveLimits::default().with_timeout(Duration::from_millis(10)));
 
-        if solved {
+        if result.outcome.is_solved() {
             solved_count += 1;
         }
     }
 
     println!(
-        "Total Count: {}\nValid Count: {}\nSolved in 10ms: {}",
+        "Engine: {}\nTotal Count: {}\nValid Count: {}\nUntargeted Count: {}\nSolved in 10ms: {}",
+        engine,
         puzzles.total_puzzles,
         puzzles.valid_puzzles.len(),
+        puzzles.untargeted_puzzles,
         solved_count
     );
 
@@ -1,18 +1,26 @@
+use cursive::traits::Nameable;
 use cursive::view::Margins;
 use cursive::views::{Button, LinearLayout, PaddedView, TextView};
-use cursive::Cursive;
+use cursive::{CbSink, Cursive};
 use petgraph::visit::EdgeRef;
 use std::cell::RefCell;
 use std::fs;
 use std::path::Path;
 use std::rc::Rc;
-use tsumego_solver::go::GoGame;
-use tsumego_solver::puzzle::{Profile, Puzzle};
+use std::thread;
+use std::time::Duration;
+use tsumego_solver::puzzle::{CancellationToken, Profile, Progress, Puzzle, PuzzleSpec, Solver};
+
+/// The name of the text view showing how far the solve has got.
+const PROGRESS_VIEW: &str = "progress";
 
 fn load_puzzle(filename: &str) -> Puzzle<Profile> {
-    let game = GoGame::from_sgf(&fs::read_to_string(Path::new(filename)).unwrap());
+    let spec = PuzzleSpec::from_sgf(&fs::read_to_string(Path::new(filename)).unwrap());
+    let (game, goal) = spec
+        .and_then(PuzzleSpec::build)
+        .unwrap_or_else(|error| panic!("Invalid puzzle: {}", error));
 
-    Puzzle::new(game)
+    Puzzle::with_goal(game, goal)
 }
 
 fn create_layer(puzzle_cell: Rc<RefCell<Puzzle<Profile>>>) -> LinearLayout {
@@ -71,14 +79,68 @@ fn create_layer(puzzle_cell: Rc<RefCell<Puzzle<Profile>>>) -> LinearLayout {
         .child(PaddedView::new(Margins::lrtb(2, 0, 0, 1), children))
 }
 
-pub fn run(filename: &str) {
-    let mut puzzle = load_puzzle(filename);
+fn format_progress(progress: Progress) -> String {
+    format!(
+        "Proof/Disproof Numbers: {:?}/{:?}\nNodes: {}\nElapsed: {:.1?}",
+        progress.proof_number, progress.disproof_number, progress.node_count, progress.elapsed
+    )
+}
+
+/// Shows the progress of the solve, with a button (or the `s` key)
+/// that stops it and explores the tree searched so far.
+fn create_progress_layer(cancellation: CancellationToken) -> LinearLayout {
+    LinearLayout::vertical()
+        .child(PaddedView::new(
+            Margins::lrtb(2, 2, 1, 1),
+            TextView::new("Solving...").with_name(PROGRESS_VIEW),
+        ))
+        .child(PaddedView::new(
+            Margins::lrtb(2, 2, 0, 1),
+            Button::new("Stop", move |_| cancellation.cancel()),
+        ))
+}
+
+/// Solves the puzzle, sending its progress to the progress view,
+/// then replaces that view with the explorer.
+fn solve(mut puzzle: Puzzle<Profile>, cancellation: CancellationToken, cb_sink: CbSink) {
+    puzzle.solve_with_progress(&cancellation, Duration::from_secs(1), |progress| {
+        let content = format_progress(progress);
 
-    puzzle.solve();
+        // Sending only fails once the interface has been closed
+        let _ = cb_sink.send(Box::new(move |s| {
+            s.call_on_name(PROGRESS_VIEW, |view: &mut TextView| {
+                view.set_content(content)
+            });
+        }));
+    });
+
+    let _ = cb_sink.send(Box::new(move |s| {
+        s.pop_layer();
+        s.add_layer(create_layer(Rc::new(RefCell::new(puzzle))));
+    }));
+}
+
+pub fn run(filename: &str) {
+    let puzzle = load_puzzle(filename);
+    let cancellation = CancellationToken::new();
 
     let mut siv = Cursive::default();
 
-    siv.add_layer(create_layer(Rc::new(RefCell::new(puzzle))));
+    siv.add_layer(create_progress_layer(cancellation.clone()));
+    siv.add_global_callback('s', {
+        let cancellation = cancellation.clone();
+        move |_| cancellation.cancel()
+    });
+
+    let cb_sink = siv.cb_sink().clone();
+    let solver = thread::spawn({
+        let cancellation = cancellation.clone();
+        move || solve(puzzle, cancellation, cb_sink)
+    });
 
     siv.run();
+
+    // Stop the solve if the interface was closed before it finished
+    cancellation.cancel();
+    solver.join().unwrap();
 }
@@ -3,26 +3,32 @@ use std::io;
 use std::path::Path;
 use std::sync::mpsc::channel;
 use std::thread;
-use std::time::Duration;
 use tsumego_solver::generation::generate_puzzle;
-use tsumego_solver::go::GoBoard;
-use tsumego_solver::puzzle::NoProfile;
+use tsumego_solver::puzzle::{Engine, NoProfile, PuzzleSpec, SolveLimits};
 
-pub fn run(output_directory: &Path, thread_count: u8) -> io::Result<()> {
+pub fn run(
+    output_directory: &Path,
+    thread_count: u8,
+    engine: Engine,
+    max_expanded_nodes: usize,
+) -> io::Result<()> {
     fs::create_dir_all(output_directory)?;
 
-    let (tx, rx) = channel::<GoBoard>();
+    let (tx, rx) = channel::<PuzzleSpec>();
 
     for _ in 0..thread_count {
         let tx = tx.clone();
         thread::spawn(move || loop {
-            let puzzle = generate_puzzle::<NoProfile>(Duration::from_secs(1));
+            let puzzle = generate_puzzle::<NoProfile>(
+                engine,
+                SolveLimits::default().with_max_expanded_nodes(max_expanded_nodes),
+            );
             tx.send(puzzle).unwrap();
         });
     }
 
     loop {
-        let puzzle = rx.recv().unwrap();
+        let puzzle = rx.recv().unwrap().board();
 
         let file = output_directory.join(format!("{:016x}.sgf", puzzle.stable_hash()));
         if file.exists() {
@@ -4,8 +4,11 @@ mod generate;
 use clap::{App, AppSettings, Arg, SubCommand};
 use std::io;
 use std::path::Path;
+use tsumego_solver::puzzle::Engine;
 
 fn main() -> io::Result<()> {
+    let engine_names: Vec<_> = Engine::all().map(|engine| engine.name()).collect();
+
     let matches = App::new("Tsumego Solver")
         .subcommand(
             SubCommand::with_name("explore")
@@ -36,6 +39,21 @@ fn main() -> io::Result<()> {
                         .long("threads")
                         .default_value("8")
                         .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("engine")
+                        .help("The search engine to solve candidates with")
+                        .long("engine")
+                        .possible_values(&engine_names)
+                        .default_value("pns")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("nodes")
+                        .help("The most nodes to expand while solving each candidate")
+                        .long("nodes")
+                        .default_value("100000")
+                        .takes_value(true),
                 ),
         )
         .setting(AppSettings::ArgRequiredElseHelp)
@@ -52,10 +70,14 @@ fn main() -> io::Result<()> {
         ("generate", Some(matches)) => {
             let output_directory = matches.value_of("out").unwrap();
             let thread_count = matches.value_of("threads").unwrap();
+            let engine = matches.value_of("engine").unwrap();
+            let max_expanded_nodes = matches.value_of("nodes").unwrap();
 
             generate::run(
                 Path::new(output_directory),
                 str::parse(thread_count).unwrap(),
+                str::parse(engine).unwrap(),
+                str::parse(max_expanded_nodes).unwrap(),
             )
         }
         _ => Ok(()),
@@ -1,19 +1,17 @@
 mod candidate;
 mod validation;
 
-use crate::go::GoBoard;
-use crate::puzzle::Profiler;
+use crate::puzzle::{Engine, Profiler, PuzzleSpec, SolveLimits};
 pub use candidate::generate_candidate;
-use std::time::Duration;
 pub use validation::validate_candidate;
 
-pub fn generate_puzzle<P: Profiler>(timeout: Duration) -> GoBoard {
+pub fn generate_puzzle<P: Profiler + 'static>(engine: Engine, limits: SolveLimits) -> PuzzleSpec {
     let mut rng = rand::thread_rng();
 
     loop {
         let candidate = generate_candidate(&mut rng);
 
-        if validate_candidate::<P>(candidate, timeout) {
+        if validate_candidate::<P>(candidate, engine, limits) {
             return candidate;
         }
     }
@@ -1,9 +1,12 @@
 mod boundary;
 
 use crate::go::{BitBoard, GoBoard, GoPlayer};
+use crate::puzzle::PuzzleSpec;
 use rand::prelude::*;
 
-pub fn generate_candidate<G: Rng>(rng: &mut G) -> GoBoard {
+/// Generates a random position, with the attacker's stones walling off the
+/// playable area. The player to move first is left for the caller to choose.
+pub fn generate_candidate<G: Rng>(rng: &mut G) -> PuzzleSpec {
     let playable_area = boundary::generate_playable_area(rng);
 
     let boundary = boundary::draw_boundary(playable_area);
@@ -22,7 +25,7 @@ pub fn generate_candidate<G: Rng>(rng: &mut G) -> GoBoard {
         GoPlayer::Black => black = black | boundary,
     };
 
-    GoBoard::new(black, white, out_of_bounds)
+    PuzzleSpec::from_board(GoBoard::new(black, white, out_of_bounds)).with_attacker(attacker)
 }
 
 fn generate_interior_stones<G: RngCore>(
@@ -62,7 +65,7 @@ mod tests {
         let candidates = iter::repeat_with(|| generate_candidate(&mut rng));
 
         for candidate in candidates.take(100) {
-            snapshot.push_str(&format!("{}\n\n", candidate));
+            snapshot.push_str(&format!("{}\n\n", candidate.board()));
         }
 
         assert_snapshot!(snapshot);
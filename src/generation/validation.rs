@@ -1,20 +1,59 @@
-use crate::go::{GoBoard, GoGame, GoPlayer};
-use crate::puzzle::Profiler;
-use crate::puzzle::Puzzle;
-use std::time::Duration;
-
-pub fn validate_candidate<P: Profiler>(candidate: GoBoard, timeout: Duration) -> bool {
-    if candidate.has_dead_groups() {
-        return false;
-    }
+use crate::go::GoPlayer;
+use crate::puzzle::{Engine, Profiler, Puzzle, PuzzleSpec, SolveLimits, SolveOutcome, Solver};
+
+/// Bounds the memory used while validating a single candidate
+/// with proof-number search, which otherwise keeps the whole tree.
+const NODE_LIMIT: usize = 1_000_000;
 
-    GoPlayer::both().all(|first_player| {
-        let mut puzzle = Puzzle::<P>::new(GoGame::from_board(candidate, *first_player));
+/// Whether the candidate is a valid spec and a puzzle whichever player
+/// moves first, solving each with `engine` within `limits`.
+///
+/// Limiting the number of expanded nodes rather than the time
+/// accepts the same candidates on any machine.
+pub fn validate_candidate<P: Profiler + 'static>(
+    candidate: PuzzleSpec,
+    engine: Engine,
+    limits: SolveLimits,
+) -> bool {
+    GoPlayer::both().all(|&first_player| {
+        let (game, goal) = match candidate.with_first_player(first_player).build() {
+            Ok(puzzle) => puzzle,
+            Err(_) => return false,
+        };
 
-        if !puzzle.solve_with_timeout(timeout) {
-            return false;
-        }
+        let mut solver: Box<dyn Solver> = match engine {
+            Engine::ProofNumber => {
+                let mut puzzle = Puzzle::<P>::with_goal(game, goal);
+                puzzle.set_node_limit(NODE_LIMIT);
 
-        puzzle.is_proved()
+                Box::new(puzzle)
+            }
+            engine => engine.solver::<P>(game, goal),
+        };
+
+        solver.search(limits).outcome == SolveOutcome::Proved
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::NoProfile;
+
+    #[test]
+    fn node_limit() {
+        let candidate =
+            PuzzleSpec::from_sgf(include_str!("../test_sgfs/puzzles/true_simple1.sgf")).unwrap();
+
+        assert!(validate_candidate::<NoProfile>(
+            candidate,
+            Engine::ProofNumber,
+            SolveLimits::default().with_max_expanded_nodes(10_000)
+        ));
+        assert!(!validate_candidate::<NoProfile>(
+            candidate,
+            Engine::ProofNumber,
+            SolveLimits::default().with_max_expanded_nodes(1)
+        ));
+    }
+}
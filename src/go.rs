@@ -3,8 +3,11 @@
 //! The current state of a game is represented by [`GoGame`](./struct.GoGame.html),
 //! and moves can be played using [`GoGame::play_move`](./struct.GoGame.html#method.play_move)
 
+mod alternating_safety;
 mod benson;
+mod binary_conversion;
 mod bit_board;
+mod seki;
 mod sgf_conversion;
 pub use bit_board::{BitBoard, BitBoardEdge, BoardPosition};
 use std::collections::hash_map::DefaultHasher;
@@ -14,7 +17,7 @@ use std::fmt::Formatter;
 use std::fmt::{Display, Write};
 use std::hash::{Hash, Hasher};
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Move {
     Pass,
     Place(BoardPosition),
@@ -64,11 +67,39 @@ impl Display for GoPlayer {
     }
 }
 
+/// Why a block can't be given external liberties,
+/// see [`GoBoard::set_external_liberties`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExternalLibertiesError {
+    /// There's no stone on the point.
+    NoStone,
+    /// The block only touches this many out of bounds points.
+    TooFewOutOfBoundsPoints(u32),
+}
+
 // Being set in both black and white denotes "out of bounds"
-#[derive(PartialEq, Clone, Copy, Debug, Eq, Hash)]
+#[derive(PartialEq, Clone, Copy, Debug, Eq)]
 pub struct GoBoard {
     white: BitBoard,
     black: BitBoard,
+    /// Stones whose blocks are never captured.
+    assumed_alive: BitBoard,
+    /// Stones whose blocks get no liberties from the out of bounds area,
+    /// only from the empty points next to them.
+    external_liberty_blocks: BitBoard,
+}
+
+impl Hash for GoBoard {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.white.hash(state);
+        self.black.hash(state);
+
+        // Boards without settings hash as they did before settings existed
+        if !(self.assumed_alive | self.external_liberty_blocks).is_empty() {
+            self.assumed_alive.hash(state);
+            self.external_liberty_blocks.hash(state);
+        }
+    }
 }
 
 impl Display for GoBoard {
@@ -98,6 +129,8 @@ impl GoBoard {
         GoBoard {
             white: BitBoard::empty(),
             black: BitBoard::empty(),
+            assumed_alive: BitBoard::empty(),
+            external_liberty_blocks: BitBoard::empty(),
         }
     }
 
@@ -105,6 +138,8 @@ impl GoBoard {
         GoBoard {
             white: white | out_of_bounds,
             black: black | out_of_bounds,
+            assumed_alive: BitBoard::empty(),
+            external_liberty_blocks: BitBoard::empty(),
         }
     }
 
@@ -113,6 +148,11 @@ impl GoBoard {
         !(self.white ^ self.black)
     }
 
+    /// Empty cells, not including out of bounds
+    pub fn in_bounds_empty_cells(&self) -> BitBoard {
+        self.empty_cells() & !self.out_of_bounds()
+    }
+
     pub fn out_of_bounds(&self) -> BitBoard {
         self.white & self.black
     }
@@ -120,6 +160,9 @@ impl GoBoard {
     pub fn set_cell(&mut self, position: BoardPosition, cell: BoardCell) {
         let mask = BitBoard::singleton(position);
 
+        self.assumed_alive = self.assumed_alive & !mask;
+        self.external_liberty_blocks = self.external_liberty_blocks & !mask;
+
         match cell {
             BoardCell::Empty => {
                 self.black = self.black & !mask;
@@ -160,8 +203,8 @@ impl GoBoard {
 
     pub fn get_bitboard_for_player(&self, player: GoPlayer) -> BitBoard {
         match player {
-            GoPlayer::Black => (self.black & !self.white),
-            GoPlayer::White => (self.white & !self.black),
+            GoPlayer::Black => self.black & !self.white,
+            GoPlayer::White => self.white & !self.black,
         }
     }
 
@@ -193,19 +236,41 @@ impl GoBoard {
 
         let group = BitBoard::singleton(position).flood_fill(mask);
 
-        !(group.expand_one() & self.empty_cells()).is_empty()
+        !(group.expand_one() & self.in_bounds_empty_cells()).is_empty()
+            || !(group & self.safe_seeds(mask)).is_empty()
+    }
+
+    /// The stones out of `stones` that keep their blocks from being captured
+    /// without needing an empty point: those assumed to be alive, and those
+    /// touching the out of bounds area in blocks without external liberties.
+    fn safe_seeds(&self, stones: BitBoard) -> BitBoard {
+        let external_liberty_blocks = self.external_liberty_blocks.flood_fill(stones);
+
+        (self.out_of_bounds().expand_one() & stones & !external_liberty_blocks)
+            | (self.assumed_alive & stones)
+    }
+
+    /// The blocks of `player` that can never be captured, because they're
+    /// assumed to be alive or are connected to the out of bounds area.
+    pub fn safe_blocks_for_player(&self, player: GoPlayer) -> BitBoard {
+        let stones = self.get_bitboard_for_player(player);
+
+        self.safe_seeds(stones).flood_fill(stones)
     }
 
     fn get_alive_groups_for_player(&self, player: GoPlayer) -> BitBoard {
         let bitboard = self.get_bitboard_for_player(player);
 
-        (self.empty_cells().expand_one() & bitboard).flood_fill(bitboard)
+        ((self.in_bounds_empty_cells().expand_one() & bitboard) | self.safe_seeds(bitboard))
+            .flood_fill(bitboard)
     }
 
     fn remove_dead_groups_for_player(&mut self, player: GoPlayer) {
         let stones_with_liberties = self.get_alive_groups_for_player(player);
+        let captured_stones = self.get_bitboard_for_player(player) & !stones_with_liberties;
 
         self.set_bitboard_for_player(player, stones_with_liberties);
+        self.external_liberty_blocks = self.external_liberty_blocks & !captured_stones;
     }
 
     pub fn has_dead_groups(&self) -> bool {
@@ -226,6 +291,71 @@ impl GoBoard {
         self.black = (self.black & !prev_out_of_bounds) | out_of_bounds;
     }
 
+    /// The stones whose blocks are assumed to be alive.
+    pub fn assumed_alive(&self) -> BitBoard {
+        self.assumed_alive
+    }
+
+    /// Assumes the blocks with a stone on one of `stones` are alive, so that
+    /// they're never captured, such as a wall that is alive outside the area
+    /// shown. Points without a stone are ignored.
+    pub fn set_assumed_alive(&mut self, stones: BitBoard) {
+        self.assumed_alive = stones & !self.empty_cells();
+    }
+
+    /// The stones whose blocks have been given external liberties.
+    pub fn external_liberty_blocks(&self) -> BitBoard {
+        self.external_liberty_blocks
+    }
+
+    /// Cuts the blocks with a stone on one of `stones` off from the out of
+    /// bounds area, so that they only have the liberties on the board, see
+    /// [`set_external_liberties`](GoBoard::set_external_liberties).
+    /// Points without a stone are ignored.
+    pub fn set_external_liberty_blocks(&mut self, stones: BitBoard) {
+        self.external_liberty_blocks = stones & !self.empty_cells();
+    }
+
+    /// Gives the block at `position` a fixed number of external liberties,
+    /// instead of it being kept alive by the out of bounds area.
+    ///
+    /// The first `liberties` out of bounds points next to the block become
+    /// empty points, which have to be filled before the block can be captured.
+    /// The block no longer gets liberties from the out of bounds area, but
+    /// stones filling its external liberties do, so they're never captured.
+    ///
+    /// Fails without changing the board if there's no stone at `position`, or if
+    /// there are fewer than `liberties` out of bounds points next to the block.
+    pub fn set_external_liberties(
+        &mut self,
+        position: BoardPosition,
+        liberties: usize,
+    ) -> Result<(), ExternalLibertiesError> {
+        if !matches!(self.get_cell(position), BoardCell::Occupied(_)) {
+            return Err(ExternalLibertiesError::NoStone);
+        }
+
+        let block =
+            BitBoard::singleton(position).flood_fill(self.get_bitboard_at_position(position));
+        let candidates = block.immediate_exterior() & self.out_of_bounds();
+
+        if (candidates.count() as usize) < liberties {
+            return Err(ExternalLibertiesError::TooFewOutOfBoundsPoints(
+                candidates.count(),
+            ));
+        }
+
+        let external_liberties = candidates
+            .positions()
+            .take(liberties)
+            .fold(BitBoard::empty(), BitBoard::set);
+
+        self.set_out_of_bounds(self.out_of_bounds() & !external_liberties);
+        self.external_liberty_blocks = self.external_liberty_blocks.set(position);
+
+        Ok(())
+    }
+
     pub fn stable_hash(&self) -> u64 {
         let mut hasher = DefaultHasher::new();
         self.hash(&mut hasher);
@@ -245,6 +375,13 @@ pub enum PassState {
 pub struct GoGame {
     ko_violations: BitBoard,
 
+    /// The player, if any, who may ignore the ko rule.
+    ko_master: Option<GoPlayer>,
+
+    /// The number of ko threats each player has elsewhere on the board.
+    black_ko_threats: u8,
+    white_ko_threats: u8,
+
     pub board: GoBoard,
 
     /// The player whose turn it currently is.
@@ -256,7 +393,7 @@ pub struct GoGame {
     pub pass_state: PassState,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum MoveError {
     Occupied,
     OutOfBounds,
@@ -270,6 +407,9 @@ impl GoGame {
         GoGame {
             board: GoBoard::empty(),
             ko_violations: BitBoard::empty(),
+            ko_master: None,
+            black_ko_threats: 0,
+            white_ko_threats: 0,
             current_player,
             pass_state: PassState::NoPass,
         }
@@ -279,11 +419,67 @@ impl GoGame {
         GoGame {
             board,
             ko_violations: BitBoard::empty(),
+            ko_master: None,
+            black_ko_threats: 0,
+            white_ko_threats: 0,
             current_player,
             pass_state: PassState::NoPass,
         }
     }
 
+    /// Lets `ko_master` ignore the ko rule, as if they had an unlimited number
+    /// of ko threats elsewhere on the board, so that they win every ko.
+    ///
+    /// The ko master never spends the threats given by `with_ko_threats`, while
+    /// the other player still needs one for each ko they retake. Passing `None`
+    /// only removes the ko master, leaving both players' threats as they were.
+    ///
+    /// ```rust
+    /// use tsumego_solver::go::{GoGame, GoPlayer};
+    ///
+    /// let game = GoGame::empty(GoPlayer::Black).with_ko_master(Some(GoPlayer::White));
+    ///
+    /// assert_eq!(game.ko_master(), Some(GoPlayer::White));
+    /// ```
+    pub fn with_ko_master(mut self, ko_master: Option<GoPlayer>) -> GoGame {
+        self.ko_master = ko_master;
+
+        self
+    }
+
+    /// The player, if any, who may ignore the ko rule.
+    pub fn ko_master(&self) -> Option<GoPlayer> {
+        self.ko_master
+    }
+
+    /// Gives each player a number of ko threats elsewhere on the board.
+    ///
+    /// A player who is banned from retaking a ko may spend a threat to retake it,
+    /// standing in for a threat and its answer being played elsewhere.
+    ///
+    /// ```rust
+    /// use tsumego_solver::go::{GoGame, GoPlayer};
+    ///
+    /// let game = GoGame::empty(GoPlayer::Black).with_ko_threats(2, 1);
+    ///
+    /// assert_eq!(game.ko_threats(GoPlayer::Black), 2);
+    /// assert_eq!(game.ko_threats(GoPlayer::White), 1);
+    /// ```
+    pub fn with_ko_threats(mut self, black: u8, white: u8) -> GoGame {
+        self.black_ko_threats = black;
+        self.white_ko_threats = white;
+
+        self
+    }
+
+    /// The number of ko threats `player` has left.
+    pub fn ko_threats(&self, player: GoPlayer) -> u8 {
+        match player {
+            GoPlayer::Black => self.black_ko_threats,
+            GoPlayer::White => self.white_ko_threats,
+        }
+    }
+
     fn get_cell(&self, position: BoardPosition) -> BoardCell {
         self.board.get_cell(position)
     }
@@ -356,9 +552,21 @@ impl GoGame {
             return Err(MoveError::Suicidal);
         }
 
-        // Evaluate ko
-        if self.ko_violations.is_set(position) {
-            return Err(MoveError::Ko);
+        let mut black_ko_threats = self.black_ko_threats;
+        let mut white_ko_threats = self.white_ko_threats;
+
+        // Evaluate ko, spending a ko threat to retake it if there is one
+        if self.ko_violations.is_set(position) && self.ko_master != Some(self.current_player) {
+            let ko_threats = match self.current_player {
+                GoPlayer::Black => &mut black_ko_threats,
+                GoPlayer::White => &mut white_ko_threats,
+            };
+
+            if *ko_threats == 0 {
+                return Err(MoveError::Ko);
+            }
+
+            *ko_threats -= 1;
         }
 
         let ko_violations = if (BitBoard::singleton(position).immediate_exterior()
@@ -374,6 +582,9 @@ impl GoGame {
 
         Ok(GoGame {
             ko_violations,
+            ko_master: self.ko_master,
+            black_ko_threats,
+            white_ko_threats,
             board: new_board,
             current_player: next_player,
             pass_state: PassState::NoPass,
@@ -395,6 +606,9 @@ impl GoGame {
         GoGame {
             board: self.board,
             ko_violations: BitBoard::empty(),
+            ko_master: self.ko_master,
+            black_ko_threats: self.black_ko_threats,
+            white_ko_threats: self.white_ko_threats,
             current_player: self.current_player.flip(),
             pass_state: match self.pass_state {
                 PassState::NoPass => PassState::PassedOnce,
@@ -540,6 +754,37 @@ mod tests {
         assert_eq!(result, Err(MoveError::Ko));
     }
 
+    #[test]
+    fn ko_master_can_retake_ko() {
+        let game = GoGame::from_sgf(include_str!("test_sgfs/ko_rule_simple.sgf"))
+            .with_ko_master(Some(GoPlayer::Black));
+
+        assert!(game.place_stone(BoardPosition::new(2, 2)).is_ok());
+    }
+
+    #[test]
+    fn ko_threat_spent_to_retake_ko() {
+        let game =
+            GoGame::from_sgf(include_str!("test_sgfs/ko_rule_simple.sgf")).with_ko_threats(1, 0);
+
+        let game = game.place_stone(BoardPosition::new(2, 2)).unwrap();
+
+        assert_eq!(game.ko_threats(GoPlayer::Black), 0);
+        assert_eq!(game.ko_threats(GoPlayer::White), 0);
+    }
+
+    #[test]
+    fn ko_master_keeps_ko_threats() {
+        let game = GoGame::from_sgf(include_str!("test_sgfs/ko_rule_simple.sgf"))
+            .with_ko_threats(1, 0)
+            .with_ko_master(Some(GoPlayer::Black));
+
+        let game = game.place_stone(BoardPosition::new(2, 2)).unwrap();
+
+        assert_eq!(game.ko_threats(GoPlayer::Black), 1);
+        assert_eq!(game.with_ko_master(None).ko_threats(GoPlayer::Black), 1);
+    }
+
     #[test]
     fn capture_two_recapture_one_not_ko_violation() {
         let game = GoGame::from_sgf(include_str!("test_sgfs/capture_two_recapture_one.sgf"));
@@ -642,6 +887,78 @@ mod tests {
         assert!(!game.has_dead_groups());
     }
 
+    #[test]
+    fn walls_touching_out_of_bounds_are_not_captured() {
+        let game = GoGame::from_sgf(include_str!("test_sgfs/walled_group.sgf"));
+
+        assert_eq!(
+            game.place_stone(BoardPosition::new(0, 1)),
+            Err(MoveError::Suicidal)
+        );
+    }
+
+    #[test]
+    fn walls_with_external_liberties_are_captured() {
+        let mut board = GoGame::from_sgf(include_str!("test_sgfs/walled_group.sgf")).board;
+        let out_of_bounds = board.out_of_bounds();
+        board
+            .set_external_liberties(BoardPosition::new(2, 0), 1)
+            .unwrap();
+
+        let external_liberties = out_of_bounds & !board.out_of_bounds();
+        assert_eq!(external_liberties.count(), 1);
+
+        let game = GoGame::from_board(board, GoPlayer::Black);
+        assert_eq!(
+            game.place_stone(BoardPosition::new(0, 1)),
+            Err(MoveError::Suicidal)
+        );
+
+        let game = game.place_stone(external_liberties.some_cell()).unwrap();
+        assert!(game
+            .board
+            .get_bitboard_for_player(GoPlayer::White)
+            .is_empty());
+        assert!(game.board.external_liberty_blocks().is_empty());
+    }
+
+    #[test]
+    fn external_liberties_need_out_of_bounds_points() {
+        let mut board = GoGame::from_sgf(include_str!("test_sgfs/walled_group.sgf")).board;
+        let unchanged = board;
+        let touching = (BitBoard::singleton(BoardPosition::new(2, 0))
+            .flood_fill(board.get_bitboard_for_player(GoPlayer::White))
+            .immediate_exterior()
+            & board.out_of_bounds())
+        .count();
+
+        assert_eq!(
+            board.set_external_liberties(BoardPosition::new(2, 0), touching as usize + 1),
+            Err(ExternalLibertiesError::TooFewOutOfBoundsPoints(touching))
+        );
+        assert_eq!(
+            board.set_external_liberties(BoardPosition::new(0, 1), 1),
+            Err(ExternalLibertiesError::NoStone)
+        );
+        assert_eq!(board, unchanged);
+    }
+
+    #[test]
+    fn assumed_alive_walls_are_not_captured() {
+        let mut board = GoGame::from_sgf(include_str!("test_sgfs/walled_group.sgf")).board;
+        board
+            .set_external_liberties(BoardPosition::new(2, 0), 0)
+            .unwrap();
+        board.set_assumed_alive(BitBoard::singleton(BoardPosition::new(2, 3)));
+
+        let game = GoGame::from_board(board, GoPlayer::Black);
+
+        assert_eq!(
+            game.place_stone(BoardPosition::new(0, 1)),
+            Err(MoveError::Suicidal)
+        );
+    }
+
     #[test]
     fn hashing_is_stable() {
         assert_eq!(GoBoard::empty().stable_hash(), 13284472273662876477);
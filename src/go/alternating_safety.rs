@@ -0,0 +1,109 @@
+use super::{BitBoard, GoGame, GoPlayer, PassState};
+
+impl GoGame {
+    /// Whether `player` can make some of their blocks unconditionally alive
+    /// (in the sense of Benson's algorithm) whatever the opponent does.
+    ///
+    /// With the opponent to move, this looks one move ahead for each side: the
+    /// opponent may play any legal move or pass, after which `player` must be able
+    /// to answer with a move that leaves them pass-alive, as with a pair of miai
+    /// eye points. With `player` to move, they must have such a move straight away.
+    ///
+    /// Answers are only looked for inside the areas that `player` encloses,
+    /// which keeps this cheap enough to run on every position. That can only
+    /// make it miss safe positions, never call an unsafe position safe.
+    pub fn is_safe_under_alternating_play(&self, player: GoPlayer) -> bool {
+        self.is_safe_under_alternating_play_by(player, |alive| !alive.is_empty())
+    }
+
+    /// Whether `player` can make every one of `stones` unconditionally alive
+    /// whatever the opponent does, in the same sense as
+    /// [`is_safe_under_alternating_play`](#method.is_safe_under_alternating_play).
+    pub fn are_stones_safe_under_alternating_play(
+        &self,
+        player: GoPlayer,
+        stones: BitBoard,
+    ) -> bool {
+        self.is_safe_under_alternating_play_by(player, |alive| (stones & !alive).is_empty())
+    }
+
+    /// Whether `player` can reach a position where `is_alive` holds
+    /// of their unconditionally alive stones whatever the opponent does.
+    fn is_safe_under_alternating_play_by(
+        &self,
+        player: GoPlayer,
+        is_alive: impl Fn(BitBoard) -> bool + Copy,
+    ) -> bool {
+        if is_alive(self.board.unconditionally_alive_blocks_for_player(player)) {
+            return true;
+        }
+
+        if self.current_player == player {
+            return self.can_become_pass_alive(player, is_alive);
+        }
+
+        // The opponent can pass too, which ends the game if they've passed already.
+        if self.pass_state != PassState::NoPass
+            || !self.pass().can_become_pass_alive(player, is_alive)
+        {
+            return false;
+        }
+
+        let empty_points = self.board.empty_cells() & !self.board.out_of_bounds();
+
+        empty_points
+            .positions()
+            .all(|position| match self.place_stone(position) {
+                Ok(game) => game.can_become_pass_alive(player, is_alive),
+                // An illegal move can't stop `player` from living.
+                Err(_) => true,
+            })
+    }
+
+    /// Whether `player`, who is to move, has a move inside an
+    /// area they enclose that makes them pass-alive.
+    fn can_become_pass_alive(&self, player: GoPlayer, is_alive: impl Fn(BitBoard) -> bool) -> bool {
+        debug_assert_eq!(self.current_player, player);
+
+        self.enclosed_empty_points(player)
+            .positions()
+            .any(|position| match self.place_stone(position) {
+                Ok(game) => is_alive(game.board.unconditionally_alive_blocks_for_player(player)),
+                Err(_) => false,
+            })
+    }
+
+    /// The empty points in regions surrounded by `player`'s stones, which
+    /// are the regions not connected to the out of bounds area.
+    fn enclosed_empty_points(&self, player: GoPlayer) -> BitBoard {
+        let regions = !self.board.get_bitboard_for_player(player);
+        let outside = self.board.out_of_bounds().flood_fill(regions);
+
+        regions & !outside & self.board.empty_cells() & !self.board.out_of_bounds()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn miai_eye_points_are_safe() {
+        let game = GoGame::from_sgf(include_str!("../test_sgfs/life_and_death/miai.sgf"));
+
+        assert!(game
+            .board
+            .unconditionally_alive_blocks_for_player(GoPlayer::White)
+            .is_empty());
+        assert!(game.is_safe_under_alternating_play(GoPlayer::White));
+        assert!(GoGame::from_board(game.board, GoPlayer::White)
+            .is_safe_under_alternating_play(GoPlayer::White));
+    }
+
+    #[test]
+    fn one_eye_is_not_safe() {
+        let game = GoGame::from_sgf(include_str!("../test_sgfs/puzzles/true_simple1.sgf"));
+
+        assert!(!game.is_safe_under_alternating_play(GoPlayer::Black));
+    }
+}
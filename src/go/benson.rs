@@ -18,6 +18,10 @@ impl GoBoard {
             let mut removed_blocks = 0;
 
             for block in remaining_blocks.groups() {
+                if !(block & self.assumed_alive).is_empty() {
+                    continue;
+                }
+
                 // Has the block `block` got at least 2 healthy regions in `regions`?
 
                 let empty_intersections_in_regions = regions & self.empty_cells();
@@ -0,0 +1,139 @@
+use super::{BitBoard, GoBoard, GoGame, GoPlayer, PassState};
+use std::convert::TryInto;
+
+impl GoPlayer {
+    pub fn to_byte(self) -> u8 {
+        match self {
+            GoPlayer::Black => 0,
+            GoPlayer::White => 1,
+        }
+    }
+
+    pub fn from_byte(byte: u8) -> Option<GoPlayer> {
+        match byte {
+            0 => Some(GoPlayer::Black),
+            1 => Some(GoPlayer::White),
+            _ => None,
+        }
+    }
+}
+
+impl GoGame {
+    /// The number of bytes in the binary encoding of a game.
+    pub const ENCODED_LENGTH: usize = 16 * 6 + 5;
+
+    /// Encodes every part of the game, including its ko state,
+    /// so that it can be decoded into an identical game.
+    pub fn to_bytes(&self) -> [u8; GoGame::ENCODED_LENGTH] {
+        let mut bytes = [0; GoGame::ENCODED_LENGTH];
+
+        let bitboards = [
+            self.board.get_bitboard_for_player(GoPlayer::Black),
+            self.board.get_bitboard_for_player(GoPlayer::White),
+            self.board.out_of_bounds(),
+            self.ko_violations,
+            self.board.assumed_alive,
+            self.board.external_liberty_blocks,
+        ];
+
+        for (chunk, bitboard) in bytes.chunks_exact_mut(16).zip(bitboards.iter()) {
+            chunk.copy_from_slice(&bitboard.to_uint().to_le_bytes());
+        }
+
+        bytes[96] = self.current_player.to_byte();
+        bytes[97] = match self.pass_state {
+            PassState::NoPass => 0,
+            PassState::PassedOnce => 1,
+            PassState::PassedTwice => 2,
+        };
+        bytes[98] = self.ko_master.map_or(2, GoPlayer::to_byte);
+        bytes[99] = self.black_ko_threats;
+        bytes[100] = self.white_ko_threats;
+
+        bytes
+    }
+
+    /// Decodes a game encoded by [`to_bytes`](GoGame::to_bytes),
+    /// or `None` if the bytes aren't a valid encoding.
+    pub fn from_bytes(bytes: &[u8; GoGame::ENCODED_LENGTH]) -> Option<GoGame> {
+        let bitboard = |index: usize| {
+            BitBoard::from_uint(u128::from_le_bytes(
+                bytes[index * 16..(index + 1) * 16].try_into().unwrap(),
+            ))
+        };
+
+        let black = bitboard(0);
+        let white = bitboard(1);
+        let out_of_bounds = bitboard(2);
+
+        if !(black & white).is_empty() {
+            return None;
+        }
+
+        let pass_state = match bytes[97] {
+            0 => PassState::NoPass,
+            1 => PassState::PassedOnce,
+            2 => PassState::PassedTwice,
+            _ => return None,
+        };
+
+        let ko_master = match bytes[98] {
+            2 => None,
+            byte => Some(GoPlayer::from_byte(byte)?),
+        };
+
+        let mut board = GoBoard::new(black, white, out_of_bounds);
+        board.assumed_alive = bitboard(4);
+        board.external_liberty_blocks = bitboard(5);
+
+        if !((board.assumed_alive | board.external_liberty_blocks) & !(black | white)).is_empty() {
+            return None;
+        }
+
+        Some(GoGame {
+            ko_violations: bitboard(3),
+            ko_master,
+            black_ko_threats: bytes[99],
+            white_ko_threats: bytes[100],
+            board,
+            current_player: GoPlayer::from_byte(bytes[96])?,
+            pass_state,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::BoardPosition;
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let game = GoGame::from_sgf(include_str!("../test_sgfs/life_and_death/ko.sgf"))
+            .with_ko_master(Some(GoPlayer::White))
+            .with_ko_threats(2, 3)
+            .pass();
+
+        assert_eq!(GoGame::from_bytes(&game.to_bytes()), Some(game));
+    }
+
+    #[test]
+    fn round_trip_with_settings() {
+        let mut board = GoGame::from_sgf(include_str!("../test_sgfs/walled_group.sgf")).board;
+        board
+            .set_external_liberties(BoardPosition::new(2, 0), 1)
+            .unwrap();
+        board.set_assumed_alive(BitBoard::singleton(BoardPosition::new(0, 0)));
+        let game = GoGame::from_board(board, GoPlayer::White);
+
+        assert_eq!(GoGame::from_bytes(&game.to_bytes()), Some(game));
+    }
+
+    #[test]
+    fn invalid_player() {
+        let mut bytes = GoGame::empty(GoPlayer::Black).to_bytes();
+        bytes[96] = 7;
+
+        assert_eq!(GoGame::from_bytes(&bytes), None);
+    }
+}
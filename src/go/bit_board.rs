@@ -2,7 +2,7 @@ use std::fmt;
 use std::fmt::{Debug, Display, Formatter};
 use std::ops::{BitAnd, BitOr, BitXor, Not};
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct BoardPosition(u8);
 
 #[derive(Clone, Copy, Debug)]
@@ -15,13 +15,13 @@ pub enum BitBoardEdge {
 
 impl BitBoardEdge {
     pub fn iter() -> impl Iterator<Item = &'static BitBoardEdge> {
-        (&[
+        [
             BitBoardEdge::Bottom,
             BitBoardEdge::Top,
             BitBoardEdge::Left,
             BitBoardEdge::Right,
-        ])
-            .iter()
+        ]
+        .iter()
     }
 
     pub fn opposite(self) -> BitBoardEdge {
@@ -121,6 +121,10 @@ impl BitBoard {
         BitBoard(int)
     }
 
+    pub fn to_uint(self) -> u128 {
+        self.0
+    }
+
     pub fn top_edge() -> BitBoard {
         BitBoard(0xFFFF_0000_0000_0000_0000_0000_0000_0000u128)
     }
@@ -0,0 +1,154 @@
+use super::{BitBoard, BoardPosition, GoBoard, GoPlayer};
+
+impl GoBoard {
+    /// Whether `player` playing at the empty point `position` would be suicide,
+    /// or would put their own stones in atari without capturing anything.
+    fn is_self_atari(&self, position: BoardPosition, player: GoPlayer) -> bool {
+        let stone = BitBoard::singleton(position);
+        let empty_cells = self.in_bounds_empty_cells() & !stone;
+        let opponent_stones = self.get_bitboard_for_player(player.flip());
+
+        let captures = (stone.immediate_exterior() & opponent_stones)
+            .flood_fill(opponent_stones)
+            .groups()
+            .any(|block| (block.immediate_exterior() & empty_cells).is_empty());
+
+        if captures {
+            return false;
+        }
+
+        let block = stone.flood_fill(self.get_bitboard_for_player(player) | stone);
+
+        (block.immediate_exterior() & empty_cells).count() <= 1
+    }
+
+    /// Whether neither player can play at the empty point `position`
+    /// without it being suicide or a self-atari that captures nothing.
+    ///
+    /// This doesn't look at what happens after the self-atari is captured,
+    /// so a point where a sacrifice would work is still reported as unplayable.
+    pub fn is_unplayable(&self, position: BoardPosition) -> bool {
+        GoPlayer::both().all(|&player| self.is_self_atari(position, player))
+    }
+
+    /// The empty points where neither player can play,
+    /// see [`is_unplayable`](GoBoard::is_unplayable).
+    pub fn unplayable_points(&self) -> BitBoard {
+        self.in_bounds_empty_cells()
+            .positions()
+            .filter(|&position| self.is_unplayable(position))
+            .fold(BitBoard::empty(), BitBoard::set)
+    }
+
+    /// The blocks of both players that are alive in seki.
+    ///
+    /// Blocks are in seki if they aren't unconditionally alive, have at least
+    /// two liberties, all of them unplayable, and share a liberty with a
+    /// block of the other player that's also in seki. Each seki must also
+    /// contain an eye, so that capturing stones sacrificed inside it can't
+    /// leave the capturing player with too little space.
+    pub fn seki_blocks(&self) -> GoBoard {
+        let unplayable_points = self.unplayable_points();
+        let empty_cells = self.in_bounds_empty_cells();
+        let alive_blocks = self.unconditionally_alive_blocks();
+
+        let mut candidates = GoBoard::new(
+            self.get_bitboard_for_player(GoPlayer::Black)
+                & !alive_blocks.get_bitboard_for_player(GoPlayer::Black),
+            self.get_bitboard_for_player(GoPlayer::White)
+                & !alive_blocks.get_bitboard_for_player(GoPlayer::White),
+            BitBoard::empty(),
+        );
+
+        loop {
+            let mut next = candidates;
+
+            for &player in GoPlayer::both() {
+                let opponent_liberties = candidates
+                    .get_bitboard_for_player(player.flip())
+                    .immediate_exterior()
+                    & empty_cells;
+
+                let remaining = candidates
+                    .get_bitboard_for_player(player)
+                    .groups()
+                    .filter(|&block| {
+                        let liberties = block.immediate_exterior() & empty_cells;
+
+                        liberties.count() >= 2
+                            && (liberties & !unplayable_points).is_empty()
+                            && !(liberties & opponent_liberties).is_empty()
+                    })
+                    .fold(BitBoard::empty(), |blocks, block| blocks | block);
+
+                next.set_bitboard_for_player(player, remaining);
+            }
+
+            if next == candidates {
+                break;
+            }
+
+            candidates = next;
+        }
+
+        let candidate_stones = candidates.get_bitboard_for_player(GoPlayer::Black)
+            | candidates.get_bitboard_for_player(GoPlayer::White);
+        let seki_area = candidate_stones | (candidate_stones.immediate_exterior() & empty_cells);
+
+        let mut seki_stones = BitBoard::empty();
+
+        for area in seki_area.groups() {
+            let has_eye = (area & unplayable_points).positions().any(|position| {
+                let neighbours =
+                    BitBoard::singleton(position).immediate_exterior() & !self.out_of_bounds();
+
+                GoPlayer::both().any(|&player| {
+                    (neighbours & !(area & candidates.get_bitboard_for_player(player))).is_empty()
+                })
+            });
+
+            if has_eye {
+                seki_stones = seki_stones | (area & candidate_stones);
+            }
+        }
+
+        GoBoard::new(
+            candidates.get_bitboard_for_player(GoPlayer::Black) & seki_stones,
+            candidates.get_bitboard_for_player(GoPlayer::White) & seki_stones,
+            BitBoard::empty(),
+        )
+    }
+
+    pub fn seki_blocks_for_player(&self, player: GoPlayer) -> BitBoard {
+        self.seki_blocks().get_bitboard_for_player(player)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::GoGame;
+    use super::*;
+
+    #[test]
+    fn seki() {
+        let board = GoGame::from_sgf(include_str!("../test_sgfs/life_and_death/seki.sgf")).board;
+        let seki_blocks = board.seki_blocks();
+
+        assert_eq!(
+            seki_blocks.get_bitboard_for_player(GoPlayer::White),
+            board.get_bitboard_for_player(GoPlayer::White)
+        );
+        assert_eq!(
+            seki_blocks.get_bitboard_for_player(GoPlayer::Black),
+            BitBoard::singleton(BoardPosition::new(3, 0))
+                .flood_fill(board.get_bitboard_for_player(GoPlayer::Black))
+        );
+    }
+
+    #[test]
+    fn no_seki() {
+        let board = GoGame::from_sgf(include_str!("../test_sgfs/puzzles/true_simple1.sgf")).board;
+
+        assert_eq!(board.seki_blocks(), GoBoard::empty());
+    }
+}
@@ -11,9 +11,9 @@ impl From<Color> for GoPlayer {
     }
 }
 
-impl Into<Color> for GoPlayer {
-    fn into(self) -> Color {
-        match self {
+impl From<GoPlayer> for Color {
+    fn from(player: GoPlayer) -> Self {
+        match player {
             GoPlayer::Black => Color::Black,
             GoPlayer::White => Color::White,
         }
@@ -30,27 +30,15 @@ impl GoGame {
 
         let first_node = nodes.next().unwrap();
 
-        let mut board = GoBoard::empty();
-        let mut triangle_locations = BitBoard::empty();
-
-        for token in first_node.tokens.iter() {
-            match token {
-                SgfToken::Add {
-                    color,
-                    coordinate: (i, j),
-                } => board.set_cell(
-                    BoardPosition::new(i - 1, j - 1),
-                    BoardCell::Occupied((*color).into()),
-                ),
-                SgfToken::Triangle { coordinate: (i, j) } => {
-                    triangle_locations = triangle_locations.set(BoardPosition::new(i - 1, j - 1));
-                }
-                SgfToken::Move { .. } => panic!("Cannot move at this time!"),
-                _ => {}
-            }
+        if first_node
+            .tokens
+            .iter()
+            .any(|token| matches!(token, SgfToken::Move { .. }))
+        {
+            panic!("Cannot move at this time!");
         }
 
-        board.set_out_of_bounds(triangle_locations.flood_fill(board.empty_cells()));
+        let board = GoBoard::from_sgf_node(first_node);
 
         let mut game = GoGame::from_board(board, GoPlayer::Black);
 
@@ -78,8 +66,80 @@ impl GoGame {
     }
 }
 
+/// The label marking a stone whose block is assumed to be alive.
+const ASSUMED_ALIVE_LABEL: &str = "A";
+/// The label marking a stone whose block has external liberties.
+const EXTERNAL_LIBERTIES_LABEL: &str = "E";
+
 impl GoBoard {
+    /// Reads the stones and out of bounds area set up by an SGF node,
+    /// ignoring any other tokens.
+    ///
+    /// Triangles mark the out of bounds area, which takes in the empty points
+    /// connected to them other than those marked with squares. Stones labelled
+    /// `A` are assumed to be alive, and stones labelled `E` only have the
+    /// liberties next to their blocks, see [`GoBoard::set_external_liberties`].
+    pub(crate) fn from_sgf_node(node: &GameNode) -> GoBoard {
+        let mut board = GoBoard::empty();
+        let mut triangle_locations = BitBoard::empty();
+        let mut square_locations = BitBoard::empty();
+        let mut assumed_alive = BitBoard::empty();
+        let mut external_liberty_blocks = BitBoard::empty();
+
+        for token in node.tokens.iter() {
+            match token {
+                SgfToken::Add {
+                    color,
+                    coordinate: (i, j),
+                } => board.set_cell(
+                    BoardPosition::new(i - 1, j - 1),
+                    BoardCell::Occupied((*color).into()),
+                ),
+                SgfToken::Triangle { coordinate: (i, j) } => {
+                    triangle_locations = triangle_locations.set(BoardPosition::new(i - 1, j - 1));
+                }
+                SgfToken::Square { coordinate: (i, j) } => {
+                    square_locations = square_locations.set(BoardPosition::new(i - 1, j - 1));
+                }
+                SgfToken::Label {
+                    label,
+                    coordinate: (i, j),
+                } => {
+                    let position = BoardPosition::new(i - 1, j - 1);
+
+                    match label.as_str() {
+                        ASSUMED_ALIVE_LABEL => assumed_alive = assumed_alive.set(position),
+                        EXTERNAL_LIBERTIES_LABEL => {
+                            external_liberty_blocks = external_liberty_blocks.set(position)
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        board.set_out_of_bounds(
+            triangle_locations.flood_fill(board.empty_cells() & !square_locations),
+        );
+        board.set_assumed_alive(assumed_alive);
+        board.set_external_liberty_blocks(external_liberty_blocks);
+
+        board
+    }
+
     pub fn to_sgf(&self) -> String {
+        let tree = GameTree {
+            nodes: vec![self.to_sgf_node()],
+            variations: Vec::new(),
+        };
+
+        tree.into()
+    }
+
+    /// The SGF node setting up the stones and out of bounds area of this board,
+    /// in the form read by [`GoBoard::from_sgf_node`].
+    pub(crate) fn to_sgf_node(self) -> GameNode {
         let mut tokens: Vec<_> = GoPlayer::both()
             .flat_map(|&go_player| {
                 let board = self.get_bitboard_for_player(go_player);
@@ -110,14 +170,72 @@ impl GoBoard {
             });
         }
 
-        let node = GameNode { tokens };
+        // Empty points next to the out of bounds area, such as external
+        // liberties, would otherwise be read as part of it
+        for position in
+            (self.in_bounds_empty_cells() & self.out_of_bounds().expand_one()).positions()
+        {
+            let (x, y) = position.to_pair();
 
-        let tree = GameTree {
-            nodes: vec![node],
-            variations: Vec::new(),
+            tokens.push(SgfToken::Square {
+                coordinate: (x + 1, y + 1),
+            });
+        }
+
+        for (stones, label) in [
+            (self.assumed_alive, ASSUMED_ALIVE_LABEL),
+            (self.external_liberty_blocks, EXTERNAL_LIBERTIES_LABEL),
+        ]
+        .iter()
+        {
+            for position in stones.positions() {
+                let (x, y) = position.to_pair();
+
+                tokens.push(SgfToken::Label {
+                    label: (*label).to_owned(),
+                    coordinate: (x + 1, y + 1),
+                });
+            }
+        }
+
+        GameNode { tokens }
+    }
+}
+
+impl Move {
+    /// Reads the move made by an SGF node, and the player making it,
+    /// if the node has exactly one move and nothing else.
+    pub(crate) fn from_sgf_node(node: &GameNode) -> Option<(Move, GoPlayer)> {
+        match node.tokens.as_slice() {
+            [SgfToken::Move { color, action }] => {
+                let go_move = match *action {
+                    Action::Pass => Move::Pass,
+                    Action::Move(i, j) => Move::Place(BoardPosition::new(i - 1, j - 1)),
+                };
+
+                Some((go_move, (*color).into()))
+            }
+            _ => None,
+        }
+    }
+
+    /// The SGF node for this move being played by `player`.
+    pub(crate) fn to_sgf_node(self, player: GoPlayer) -> GameNode {
+        let action = match self {
+            Move::Pass => Action::Pass,
+            Move::Place(position) => {
+                let (x, y) = position.to_pair();
+
+                Action::Move(x + 1, y + 1)
+            }
         };
 
-        tree.into()
+        GameNode {
+            tokens: vec![SgfToken::Move {
+                color: player.into(),
+                action,
+            }],
+        }
     }
 }
 
@@ -131,10 +249,21 @@ mod tests {
 
     impl Arbitrary for GoBoard {
         fn arbitrary<G: Gen>(g: &mut G) -> GoBoard {
-            generation::generate_candidate(g)
+            generation::generate_candidate(g).board()
         }
     }
 
+    #[test]
+    fn settings_round_trip() {
+        let mut board = GoGame::from_sgf(include_str!("../test_sgfs/walled_group.sgf")).board;
+        board
+            .set_external_liberties(BoardPosition::new(2, 0), 2)
+            .unwrap();
+        board.set_assumed_alive(BitBoard::singleton(BoardPosition::new(0, 0)));
+
+        assert_eq!(GoGame::from_sgf(&board.to_sgf()).board, board);
+    }
+
     #[quickcheck]
     fn inverse(board: GoBoard) {
         assert_eq!(GoGame::from_sgf(&board.to_sgf()).board, board);
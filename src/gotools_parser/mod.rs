@@ -1,7 +1,5 @@
-use crate::{
-    go::{BoardCell, BoardPosition, GoBoard, GoGame, GoPlayer},
-    puzzle::{NoProfile, Puzzle},
-};
+use crate::go::{BitBoard, BoardCell, BoardPosition, GoBoard, GoPlayer};
+use crate::puzzle::PuzzleSpec;
 use pest::{iterators::Pair, Parser};
 use std::error::Error;
 
@@ -11,6 +9,7 @@ struct GoToolsParser;
 
 struct StoneSet {
     points: Vec<(u8, u8, GoPlayer)>,
+    marked_chains: Vec<(u8, u8)>,
 }
 
 impl StoneSet {
@@ -24,12 +23,25 @@ impl StoneSet {
         for point in self.points.iter_mut() {
             *point = (point.1, 18 - point.0, point.2);
         }
+
+        for point in self.marked_chains.iter_mut() {
+            *point = (point.1, 18 - point.0);
+        }
     }
 }
 
 pub struct PuzzleCollection {
     pub total_puzzles: u32,
-    pub valid_puzzles: Vec<Puzzle<NoProfile>>,
+    pub valid_puzzles: Vec<PuzzleSpec>,
+    /// The number of valid puzzles without marked chains of a single colour,
+    /// which are read with a guessed attacker and no targets.
+    pub untargeted_puzzles: u32,
+}
+
+impl Default for PuzzleCollection {
+    fn default() -> PuzzleCollection {
+        PuzzleCollection::new()
+    }
 }
 
 impl PuzzleCollection {
@@ -37,49 +49,67 @@ impl PuzzleCollection {
         PuzzleCollection {
             total_puzzles: 0,
             valid_puzzles: Vec::new(),
+            untargeted_puzzles: 0,
         }
     }
 
     pub fn append(&mut self, mut other: PuzzleCollection) {
         self.total_puzzles += other.total_puzzles;
         self.valid_puzzles.append(&mut other.valid_puzzles);
+        self.untargeted_puzzles += other.untargeted_puzzles;
     }
 }
 
 fn char_to_int(character: char) -> u8 {
-    (character as u8) - ('A' as u8)
+    (character as u8) - b'A'
 }
 
-fn read_puzzle(pair: Pair<Rule>) -> Option<Puzzle<NoProfile>> {
+fn read_location(pair: Pair<Rule>) -> (u8, u8) {
+    let mut chars = pair.as_str().chars();
+
+    (
+        char_to_int(chars.next().unwrap()),
+        char_to_int(chars.next().unwrap()),
+    )
+}
+
+/// Reads a puzzle with Black to move, whose targets are the chains marked
+/// as deciding the problem. These belong to the defender, so if there are
+/// none, or there are marked chains of both colours, the attacker is guessed
+/// instead and the defender has to make any of their blocks live.
+fn read_puzzle(pair: Pair<Rule>) -> Option<PuzzleSpec> {
     let puzzle_definition = pair
         .into_inner()
         .find(|inner_pair| inner_pair.as_rule() == Rule::puzzle_definition)
         .unwrap();
 
-    let stones = puzzle_definition
-        .into_inner()
-        .filter(|pair| pair.as_rule() == Rule::stone);
-
-    let mut board = GoBoard::empty();
-
-    let mut stone_set = StoneSet {
-        points: stones
-            .map(|stone| {
-                let mut chars = stone.as_str().chars();
+    let mut stones = Vec::new();
+    let mut marked_chains = Vec::new();
 
-                let player = if chars.next().unwrap() == '?' {
+    for pair in puzzle_definition.into_inner() {
+        match pair.as_rule() {
+            Rule::stone => {
+                let player = if pair.as_str().starts_with('?') {
                     GoPlayer::White
                 } else {
                     GoPlayer::Black
                 };
+                let (x, y) = read_location(pair.into_inner().next().unwrap());
+
+                stones.push((x, y, player));
+            }
+            Rule::alive_chain => {
+                marked_chains.push(read_location(pair.into_inner().next().unwrap()))
+            }
+            _ => {}
+        }
+    }
 
-                (
-                    char_to_int(chars.next().unwrap()),
-                    char_to_int(chars.next().unwrap()),
-                    player,
-                )
-            })
-            .collect(),
+    let mut board = GoBoard::empty();
+
+    let mut stone_set = StoneSet {
+        points: stones,
+        marked_chains,
     };
 
     let mut rotations = 0;
@@ -105,7 +135,28 @@ fn read_puzzle(pair: Pair<Rule>) -> Option<Puzzle<NoProfile>> {
         .unwrap();
     board.set_out_of_bounds(out_of_bounds);
 
-    Some(Puzzle::new(GoGame::from_board(board, GoPlayer::Black)))
+    let marked_chains = stone_set
+        .marked_chains
+        .into_iter()
+        .fold(BitBoard::empty(), |marked_chains, (x, y)| {
+            marked_chains.set(BoardPosition::new(x, y))
+        });
+
+    let marked_players: Vec<_> = GoPlayer::both()
+        .copied()
+        .filter(|&player| !(marked_chains & board.get_bitboard_for_player(player)).is_empty())
+        .collect();
+
+    let spec = match marked_players.as_slice() {
+        [defender] => PuzzleSpec::from_board(board)
+            .with_defender(*defender)
+            .with_targets(marked_chains),
+        _ => PuzzleSpec::from_board(board),
+    };
+
+    spec.build().ok()?;
+
+    Some(spec)
 }
 
 pub fn parse(contents: &str) -> Result<PuzzleCollection, Box<dyn Error>> {
@@ -117,8 +168,12 @@ pub fn parse(contents: &str) -> Result<PuzzleCollection, Box<dyn Error>> {
 
     let mut total_puzzles = 0;
     let mut valid_puzzles = Vec::new();
+    let mut untargeted_puzzles = 0;
     for pair in puzzle_pairs {
         if let Some(puzzle) = read_puzzle(pair) {
+            if puzzle.targets().is_empty() {
+                untargeted_puzzles += 1;
+            }
             valid_puzzles.push(puzzle);
         }
         total_puzzles += 1;
@@ -127,6 +182,7 @@ pub fn parse(contents: &str) -> Result<PuzzleCollection, Box<dyn Error>> {
     Ok(PuzzleCollection {
         valid_puzzles,
         total_puzzles,
+        untargeted_puzzles,
     })
 }
 
@@ -145,9 +201,49 @@ mod tests {
         let mut string = String::new();
 
         for puzzle in puzzles.valid_puzzles {
-            string.push_str(format!("{}\n", puzzle.current_game().board).borrow());
+            string.push_str(format!("{}\n", puzzle.board()).borrow());
         }
 
         assert_snapshot!(string);
     }
+
+    #[test]
+    fn marked_chains_are_on_stones() {
+        let contents = include_str!("example_puzzles");
+
+        let puzzles = parse(contents).unwrap();
+
+        for puzzle in puzzles.valid_puzzles {
+            let stones = puzzle.stones(GoPlayer::Black) | puzzle.stones(GoPlayer::White);
+
+            assert_eq!(puzzle.targets() & !stones, BitBoard::empty());
+        }
+    }
+
+    #[test]
+    fn puzzles_without_single_coloured_marks_are_kept() {
+        let contents = "header\n\
+                        $ P1\n\
+                        ?AB?AC?AEAAF?BA?BBABC?BD?BEABF?CAACBACDACEACFADCADDAEAAEBAEC\n\
+                        \n\
+                        $ P2\n\
+                        ?AB?AC?AEAAF?BA?BBABC?BD?BEABF?CAACBACDACEACFADCADDAEAAEBAEC[AB][AC]\n\
+                        \n\
+                        $ P3\n\
+                        ?AB?AC?AEAAF?BA?BBABC?BD?BEABF?CAACBACDACEACFADCADDAEAAEBAEC[AB][BC]\n\
+                        \n";
+
+        let puzzles = parse(contents).unwrap();
+
+        assert_eq!(puzzles.total_puzzles, 3);
+        assert_eq!(puzzles.valid_puzzles.len(), 3);
+        assert_eq!(puzzles.untargeted_puzzles, 2);
+
+        for &index in &[0, 2] {
+            let puzzle = puzzles.valid_puzzles[index];
+
+            assert_eq!(puzzle.attacker(), None);
+            assert_eq!(puzzle.targets(), BitBoard::empty());
+        }
+    }
 }
@@ -1,16 +1,48 @@
+mod alpha_beta;
+mod checkpoint;
+mod dfpn;
+mod lambda_search;
+mod leaf_evaluator;
+mod outcome;
+mod parallel_dfpn;
 mod profiler;
+mod progress;
+mod proof_checker;
 mod proof_number;
+mod proof_tree;
+mod puzzle_spec;
+mod relevance_zone;
+mod solve_limits;
+mod solver;
+mod status;
 mod terminal_detection;
 
-use crate::go::{GoGame, GoPlayer, Move};
+use crate::go::{GoGame, GoPlayer, Move, MoveError};
+pub use alpha_beta::AlphaBeta;
+pub use checkpoint::CheckpointError;
+pub use dfpn::Dfpn;
+pub use lambda_search::LambdaSearch;
+pub use leaf_evaluator::{ConstantEvaluator, KnowledgeEvaluator, LeafEvaluator};
+pub use outcome::{KoResult, Outcome};
+pub use parallel_dfpn::ParallelDfpn;
 use petgraph::stable_graph::NodeIndex;
 use petgraph::stable_graph::StableGraph;
 use petgraph::visit::EdgeRef;
 use petgraph::Direction;
 pub use profiler::{NoProfile, Profile, Profiler};
-use proof_number::ProofNumber;
+pub use progress::{CancellationToken, Progress};
+pub use proof_checker::{ProofError, ProofErrorKind};
+pub use proof_number::ProofNumber;
+pub use proof_tree::{ProofTree, ProofTreeMismatch};
+pub use puzzle_spec::{PuzzleSpec, PuzzleSpecError};
+pub use solve_limits::{SolveLimits, SolveOutcome};
+pub use solver::{Engine, Goal, SolveResult, SolveStats, Solver, UnknownEngine};
+pub use status::{Classification, Status};
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::fmt::{Debug, Formatter};
+use std::mem;
+use std::sync::Arc;
 use std::time::Duration;
 use std::time::Instant;
 
@@ -38,10 +70,25 @@ impl Debug for NodeType {
     }
 }
 
+/// The result of playing a move, for the player making it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveStatus {
+    Win,
+    Loss,
+    /// Neither proved nor disproved within the search budget.
+    Unknown,
+}
+
 #[derive(Clone, Copy)]
 pub struct AndOrNode {
     proof_number: ProofNumber,
     disproof_number: ProofNumber,
+    /// The iteration in which this node was last on the most-proving path.
+    last_visited: u32,
+    /// Whether the numbers depend on the path taken to reach this node,
+    /// because they were worked out from a repetition of a position on it.
+    /// Such nodes are never shared with another parent.
+    is_path_dependent: bool,
 }
 
 impl Debug for AndOrNode {
@@ -62,6 +109,8 @@ impl AndOrNode {
         AndOrNode {
             proof_number: ProofNumber::finite(1),
             disproof_number: ProofNumber::finite(1),
+            last_visited: 0,
+            is_path_dependent: false,
         }
     }
 
@@ -70,11 +119,15 @@ impl AndOrNode {
             AndOrNode {
                 proof_number: ProofNumber::finite(0),
                 disproof_number: ProofNumber::infinite(),
+                last_visited: 0,
+                is_path_dependent: false,
             }
         } else {
             AndOrNode {
                 proof_number: ProofNumber::infinite(),
                 disproof_number: ProofNumber::finite(0),
+                last_visited: 0,
+                is_path_dependent: false,
             }
         }
     }
@@ -92,14 +145,45 @@ impl AndOrNode {
     }
 }
 
+/// Guesses the attacker from which player has stones
+/// touching the out of bounds area.
+fn guess_attacker(game: GoGame) -> GoPlayer {
+    if !(game.board.out_of_bounds().expand_one()
+        & game.board.get_bitboard_for_player(GoPlayer::White))
+    .is_empty()
+    {
+        GoPlayer::White
+    } else {
+        GoPlayer::Black
+    }
+}
+
 pub struct Puzzle<P: Profiler> {
     player: GoPlayer,
-    attacker: GoPlayer,
+    /// What each player is trying to achieve.
+    goal: Goal,
     pub tree: StableGraph<AndOrNode, Move>,
     pub root_id: NodeIndex,
     pub current_node_id: NodeIndex,
+    /// Maps each position in the tree to the node representing it, so
+    /// that transpositions share a single node with several parents.
+    transposition_table: HashMap<GoGame, NodeIndex>,
+    node_stack: Vec<NodeIndex>,
     game_stack: Vec<GoGame>,
+    /// Positions played before the root, which count as repetitions
+    /// in the same way as those on the current path.
+    previous_games: Vec<GoGame>,
     current_type: NodeType,
+    /// The number of iterations run so far, used to find
+    /// the least recently visited subtrees.
+    iteration: u32,
+    /// The maximum number of nodes kept in the tree before
+    /// the least recently visited subtrees are collapsed.
+    node_limit: Option<usize>,
+    /// Gives new leaves their initial proof and disproof numbers.
+    leaf_evaluator: Arc<dyn LeafEvaluator + Send + Sync>,
+    /// Whether moves outside the relevance zone are left out of the search.
+    prunes_irrelevant_moves: bool,
     pub profiler: P,
 }
 
@@ -107,31 +191,51 @@ impl<P: Profiler> Puzzle<P> {
     pub fn new(game: GoGame) -> Puzzle<P> {
         // debug_assert_eq!(game.plys(), 0);
 
-        let attacker = if !(game.board.out_of_bounds().expand_one()
-            & game.board.get_bitboard_for_player(GoPlayer::White))
-        .is_empty()
-        {
-            GoPlayer::White
-        } else {
-            GoPlayer::Black
-        };
+        Self::with_goal(game, Goal::guess(game))
+    }
 
-        let player = game.current_player;
+    /// Limits the number of nodes kept in the tree.
+    ///
+    /// When the limit is reached, the least recently visited unsolved subtrees
+    /// are collapsed back into leaves, keeping their last proof and disproof
+    /// numbers, and are expanded again if the search returns to them.
+    pub fn set_node_limit(&mut self, node_limit: usize) {
+        self.node_limit = Some(node_limit);
+    }
 
-        let mut tree = StableGraph::<AndOrNode, Move>::new();
+    /// Sets how new leaves get their initial proof and disproof numbers.
+    ///
+    /// By default every leaf starts with both numbers at 1.
+    pub fn set_leaf_evaluator<E: LeafEvaluator + Send + Sync + 'static>(&mut self, evaluator: E) {
+        self.leaf_evaluator = Arc::new(evaluator);
+    }
 
-        let root_id = tree.add_node(AndOrNode::create_non_terminal_leaf());
+    /// Sets whether to leave out moves that can't change the result.
+    ///
+    /// These are moves in empty regions walled off by safe attacker stones
+    /// that are too small for the defender to live in, which leave the life
+    /// of the defender exactly as a pass would.
+    pub fn set_relevance_zone_pruning(&mut self, prunes_irrelevant_moves: bool) {
+        self.prunes_irrelevant_moves = prunes_irrelevant_moves;
+    }
 
-        Puzzle {
-            player,
-            attacker,
-            tree,
-            root_id,
-            current_node_id: root_id,
-            game_stack: vec![game],
-            current_type: NodeType::Or,
-            profiler: P::new(),
-        }
+    /// Limits the approximate number of bytes used by the tree.
+    /// See [`set_node_limit`](#method.set_node_limit).
+    ///
+    /// The limit is converted to a node limit using a rough estimate of the
+    /// size of each node, which leaves out allocator overhead and spare
+    /// capacity, so actual memory use can be somewhat higher.
+    pub fn set_memory_limit(&mut self, bytes: usize) {
+        self.set_node_limit(bytes / Self::estimated_bytes_per_node());
+    }
+
+    /// An estimate of the memory used by each node, its incoming edge,
+    /// its entry in the transposition table and its share of the table's
+    /// control bytes, assuming the table is at its maximum load factor of 7/8.
+    fn estimated_bytes_per_node() -> usize {
+        mem::size_of::<(AndOrNode, [NodeIndex; 2])>()
+            + mem::size_of::<(Move, [NodeIndex; 4])>()
+            + (mem::size_of::<(GoGame, NodeIndex)>() + 1) * 8 / 7
     }
 
     pub fn from_sgf(sgf_string: &str) -> Puzzle<P> {
@@ -142,37 +246,152 @@ impl<P: Profiler> Puzzle<P> {
         *self.game_stack.last().unwrap()
     }
 
+    /// What each player is trying to achieve.
+    pub fn goal(&self) -> Goal {
+        self.goal
+    }
+
+    /// The positions played before the root, such as the old roots
+    /// left behind by [`reroot`](#method.reroot).
+    pub fn previous_games(&self) -> &[GoGame] {
+        &self.previous_games
+    }
+
     fn develop_current_node(&mut self) {
         debug_assert!(self.tree.neighbors(self.current_node_id).next().is_none());
 
         let game = self.current_game();
 
-        let moves = game.generate_moves();
+        let mut moves = game.generate_moves();
+
+        if self.prunes_irrelevant_moves {
+            relevance_zone::prune_moves(game, self.goal.attacker, self.goal.targets, &mut moves);
+        }
 
         debug_assert!(!moves.is_empty(), "No moves found for node: {:?}", game);
 
-        self.profiler.expand_node(game, moves.len() as u8);
+        let mut new_node_count = 0;
 
         for (child, board_move) in moves.iter().rev() {
-            let new_node = if let Some(game_theoretic_value) =
-                terminal_detection::is_terminal(*child, self.player, self.attacker)
-            {
-                AndOrNode::create_terminal(game_theoretic_value)
-            } else {
-                AndOrNode::create_non_terminal_leaf()
-            };
+            // A move back to a position on the current path is treated as illegal,
+            // as it would be under a positional superko rule, by making it a loss
+            // for the player making it. Otherwise a player who can ignore the
+            // ko rule could keep the game going forever.
+            if self.game_stack.contains(child) || self.previous_games.contains(child) {
+                let mut new_node = AndOrNode::create_terminal(child.current_player == self.player);
+                new_node.last_visited = self.iteration;
+                new_node.is_path_dependent = true;
+
+                let new_node_id = self.tree.add_node(new_node);
+                new_node_count += 1;
+
+                self.tree
+                    .add_edge(self.current_node_id, new_node_id, *board_move);
+
+                continue;
+            }
+
+            let child_id = match self.transposition_table.get(child) {
+                // Linking to an ancestor would introduce a cycle, and the numbers of
+                // a path dependent node may be wrong on this path, so in those cases
+                // the position gets a separate node of its own.
+                Some(&existing_id)
+                    if !self.tree[existing_id].is_path_dependent
+                        && !self.is_ancestor(existing_id) =>
+                {
+                    existing_id
+                }
+                existing => {
+                    let replaces_entry = existing
+                        .is_none_or(|&existing_id| self.tree[existing_id].is_path_dependent);
+
+                    let mut new_node = self.create_leaf(child);
+                    new_node.last_visited = self.iteration;
 
-            let new_node_id = self.tree.add_node(new_node);
+                    let new_node_id = self.tree.add_node(new_node);
+                    new_node_count += 1;
+
+                    if replaces_entry {
+                        self.transposition_table.insert(*child, new_node_id);
+                    }
+
+                    new_node_id
+                }
+            };
 
             self.tree
-                .add_edge(self.current_node_id, new_node_id, *board_move);
+                .add_edge(self.current_node_id, child_id, *board_move);
         }
 
+        self.profiler.expand_node(game, new_node_count);
+
         // Bump up max depth if necessary.
         self.profiler.move_down();
         self.profiler.move_up();
     }
 
+    /// Creates a leaf for a position, which is solved if the position is
+    /// terminal and otherwise has numbers from the leaf evaluator.
+    fn create_leaf(&self, game: &GoGame) -> AndOrNode {
+        if let Some(status) =
+            terminal_detection::is_terminal(*game, self.goal.attacker, self.goal.targets)
+        {
+            return AndOrNode::create_terminal(
+                status.winner(self.goal.attacker, self.goal.seki_is_life) == self.player,
+            );
+        }
+
+        let (kill_cost, live_cost) = self.leaf_evaluator.evaluate(game, self.goal.attacker);
+        let (proof_cost, disproof_cost) = if self.player == self.goal.attacker {
+            (kill_cost, live_cost)
+        } else {
+            (live_cost, kill_cost)
+        };
+
+        let mut node = AndOrNode::create_non_terminal_leaf();
+        node.proof_number = ProofNumber::finite(proof_cost.max(1));
+        node.disproof_number = ProofNumber::finite(disproof_cost.max(1));
+
+        node
+    }
+
+    /// Whether `node_id` is the current node or one of its ancestors,
+    /// through any of the paths from the root.
+    ///
+    /// Unless `node_id` is on the current path or is a leaf, this walks every
+    /// ancestor of the current node, which can be most of the tree. It is only
+    /// needed for transpositions to nodes that have already been expanded.
+    fn is_ancestor(&self, node_id: NodeIndex) -> bool {
+        if self.node_stack.contains(&node_id) {
+            return true;
+        }
+
+        // A leaf can't reach the current node, so linking to it can't form a cycle.
+        if self.tree.neighbors(node_id).next().is_none() {
+            return false;
+        }
+
+        let mut visited = HashSet::new();
+        let mut stack = vec![self.current_node_id];
+
+        while let Some(ancestor_id) = stack.pop() {
+            if ancestor_id == node_id {
+                return true;
+            }
+
+            for parent_id in self
+                .tree
+                .neighbors_directed(ancestor_id, Direction::Incoming)
+            {
+                if visited.insert(parent_id) {
+                    stack.push(parent_id);
+                }
+            }
+        }
+
+        false
+    }
+
     fn select_most_proving_node(&mut self) {
         loop {
             let mut outgoing_edges = self.tree.edges(self.current_node_id);
@@ -215,23 +434,23 @@ impl<P: Profiler> Puzzle<P> {
     }
 
     pub fn move_down(&mut self, node_id: NodeIndex, go_move: Move) {
+        self.tree[node_id].last_visited = self.iteration;
         self.current_node_id = node_id;
         self.current_type = self.current_type.flip();
+        self.node_stack.push(node_id);
         self.game_stack
             .push(self.current_game().play_move(go_move).unwrap());
 
         self.profiler.move_down();
     }
 
+    /// Moves back up to the node that the current node was reached from.
     pub fn move_up(&mut self) -> bool {
-        if let Some(parent_node_id) = self
-            .tree
-            .neighbors_directed(self.current_node_id, Direction::Incoming)
-            .next()
-        {
-            self.current_node_id = parent_node_id;
-            self.current_type = self.current_type.flip();
+        if self.node_stack.len() > 1 {
+            self.node_stack.pop();
             self.game_stack.pop();
+            self.current_node_id = *self.node_stack.last().unwrap();
+            self.current_type = self.current_type.flip();
 
             self.profiler.move_up();
 
@@ -241,6 +460,67 @@ impl<P: Profiler> Puzzle<P> {
         }
     }
 
+    /// Makes the position reached by playing `go_move` at the root the new root,
+    /// keeping the search below it and discarding the rest of the tree.
+    ///
+    /// The old root counts as a repetition from then on, like the positions before
+    /// the original root. If the new root was solved and its children were pruned,
+    /// it goes back to being a leaf so that a winning move can be found again.
+    pub fn reroot(&mut self, go_move: Move) -> Result<(), MoveError> {
+        let root_game = self.game_stack[0];
+        let game = root_game.play_move(go_move)?;
+
+        if game == root_game || self.previous_games.contains(&game) {
+            return Err(MoveError::Ko);
+        }
+
+        let existing_id = self
+            .tree
+            .edges(self.root_id)
+            .find(|edge| *edge.weight() == go_move)
+            .map(|edge| edge.target());
+
+        let new_root_id = match existing_id {
+            Some(node_id) => node_id,
+            None => self.tree.add_node(AndOrNode::create_non_terminal_leaf()),
+        };
+
+        let mut walker = self.tree.neighbors(self.root_id).detach();
+        while let Some(edge_id) = walker.next_edge(&self.tree) {
+            self.tree.remove_edge(edge_id);
+        }
+
+        self.root_id = new_root_id;
+        self.remove_unreachable_nodes();
+
+        // The numbers were for the old root's player winning.
+        let node_ids: Vec<_> = self.tree.node_indices().collect();
+        for node_id in node_ids {
+            let node = &mut self.tree[node_id];
+            mem::swap(&mut node.proof_number, &mut node.disproof_number);
+        }
+
+        self.player = game.current_player;
+        self.previous_games.push(root_game);
+        self.transposition_table.insert(game, new_root_id);
+        self.current_node_id = new_root_id;
+        self.current_type = NodeType::Or;
+        self.node_stack = vec![new_root_id];
+        self.game_stack = vec![game];
+
+        let is_pruned =
+            self.tree[new_root_id].is_solved() && self.tree.neighbors(new_root_id).next().is_none();
+
+        if existing_id.is_none() || is_pruned {
+            let mut node = self.create_leaf(&game);
+            node.last_visited = self.iteration;
+
+            self.tree[new_root_id] = node;
+        }
+
+        Ok(())
+    }
+
     pub fn solve(&mut self) {
         while !self.is_solved() {
             self.solve_iteration();
@@ -248,17 +528,81 @@ impl<P: Profiler> Puzzle<P> {
     }
 
     pub fn solve_with_timeout(&mut self, timeout: Duration) -> bool {
-        let timeout_at = Instant::now() + timeout;
+        self.solve_with_limits(SolveLimits::default().with_timeout(timeout))
+            .is_solved()
+    }
 
-        while !self.is_solved() {
-            if Instant::now() > timeout_at {
-                return false;
+    /// Searches until the puzzle is solved or one of the limits is reached.
+    ///
+    /// Expanded nodes are counted from the start of this call. The tree size
+    /// is checked after any collapsing of subtrees to keep within the node limit.
+    pub fn solve_with_limits(&mut self, limits: SolveLimits) -> SolveOutcome {
+        let timeout_at = limits.timeout.map(|timeout| Instant::now() + timeout);
+        let start_iteration = self.iteration;
+
+        loop {
+            let root = self.root_node();
+
+            if root.is_proved() {
+                return SolveOutcome::Proved;
+            } else if root.is_disproved() {
+                return SolveOutcome::Disproved;
+            } else if timeout_at.is_some_and(|timeout_at| Instant::now() > timeout_at) {
+                return SolveOutcome::TimedOut;
+            } else if limits.max_expanded_nodes.is_some_and(|max_expanded_nodes| {
+                (self.iteration - start_iteration) as usize >= max_expanded_nodes
+            }) {
+                return SolveOutcome::NodeLimit;
+            } else if limits
+                .max_tree_size
+                .is_some_and(|max_tree_size| self.tree.node_count() > max_tree_size)
+            {
+                return SolveOutcome::MemoryLimit;
             }
 
             self.solve_iteration();
         }
+    }
 
-        true
+    /// Searches until the puzzle is solved or `cancellation` is cancelled,
+    /// returning whether it was solved.
+    ///
+    /// `on_progress` is called when the search starts, then at most once every
+    /// `progress_interval` between iterations, and once more when it stops.
+    pub fn solve_with_progress<F: FnMut(Progress)>(
+        &mut self,
+        cancellation: &CancellationToken,
+        progress_interval: Duration,
+        mut on_progress: F,
+    ) -> bool {
+        let start = Instant::now();
+        let mut next_progress_at = start;
+
+        while !self.is_solved() && !cancellation.is_cancelled() {
+            let now = Instant::now();
+
+            if now >= next_progress_at {
+                on_progress(self.progress(now - start));
+                next_progress_at = now + progress_interval;
+            }
+
+            self.solve_iteration();
+        }
+
+        on_progress(self.progress(start.elapsed()));
+
+        self.is_solved()
+    }
+
+    fn progress(&self, elapsed: Duration) -> Progress {
+        let root = self.root_node();
+
+        Progress {
+            proof_number: root.proof_number,
+            disproof_number: root.disproof_number,
+            node_count: self.tree.node_count(),
+            elapsed,
+        }
     }
 
     pub fn is_solved(&self) -> bool {
@@ -270,33 +614,185 @@ impl<P: Profiler> Puzzle<P> {
     }
 
     fn solve_iteration(&mut self) {
+        self.iteration += 1;
         self.select_most_proving_node();
         self.develop_current_node();
         self.update_ancestors();
+
+        if let Some(node_limit) = self.node_limit {
+            if self.tree.node_count() > node_limit {
+                self.collect_garbage(node_limit);
+            }
+        }
+    }
+
+    /// Collapses the least recently visited unsolved subtrees back into leaves
+    /// until the tree is comfortably within `node_limit`.
+    ///
+    /// Must be called while the current node is the root.
+    fn collect_garbage(&mut self, node_limit: usize) {
+        debug_assert_eq!(self.current_node_id, self.root_id);
+
+        let target_node_count = node_limit - node_limit / 4;
+
+        loop {
+            let mut candidates: Vec<_> = self
+                .tree
+                .node_indices()
+                .filter(|&node_id| {
+                    node_id != self.root_id
+                        && !self.tree[node_id].is_solved()
+                        && self.tree.neighbors(node_id).next().is_some()
+                })
+                .collect();
+
+            if candidates.is_empty() {
+                break;
+            }
+
+            candidates.sort_by_key(|&node_id| self.tree[node_id].last_visited);
+
+            // Collapse the older half, or at least one subtree.
+            let collapse_count = (candidates.len() / 2).max(1);
+            for &node_id in candidates[..collapse_count].iter() {
+                let mut walker = self.tree.neighbors(node_id).detach();
+                while let Some(edge_id) = walker.next_edge(&self.tree) {
+                    self.tree.remove_edge(edge_id);
+                }
+            }
+
+            self.remove_unreachable_nodes();
+
+            if self.tree.node_count() <= target_node_count {
+                break;
+            }
+        }
+    }
+
+    /// Removes all nodes that can no longer be reached from the root,
+    /// along with their entries in the transposition table.
+    fn remove_unreachable_nodes(&mut self) {
+        let mut reachable = HashSet::new();
+        let mut stack = vec![self.root_id];
+
+        while let Some(node_id) = stack.pop() {
+            if reachable.insert(node_id) {
+                stack.extend(self.tree.neighbors(node_id));
+            }
+        }
+
+        let unreachable: Vec<_> = self
+            .tree
+            .node_indices()
+            .filter(|node_id| !reachable.contains(node_id))
+            .collect();
+
+        for node_id in unreachable {
+            self.tree.remove_node(node_id);
+        }
+
+        self.transposition_table
+            .retain(|_, node_id| reachable.contains(node_id));
     }
 
+    /// Propagates the new proof and disproof numbers of the current node
+    /// to all of its ancestors.
+    ///
+    /// The path that was walked down is updated first, pruning any nodes
+    /// along it that become solved. Other parents of shared nodes are then
+    /// updated in turn, after which the search restarts from the root.
+    ///
+    /// Nodes only become path dependent while on the path, so this is also
+    /// where they stop being shared. Their other parents are given a new leaf
+    /// in their place, so that the position is searched again from there.
     fn update_ancestors(&mut self) {
+        let mut pending = Vec::new();
+        // The nodes in `pending`, so that a node shared by many
+        // updated children is only queued once.
+        let mut queued = HashSet::new();
+
         loop {
-            let has_changed = self.set_proof_and_disproof_numbers();
+            let has_changed =
+                self.set_proof_and_disproof_numbers(self.current_node_id, self.current_type);
 
             if !has_changed {
                 break;
             }
 
-            self.prune_if_solved();
+            // A pruned node's index can be reused by a new leaf below,
+            // so it mustn't be left waiting to be updated.
+            for node_id in self.prune_if_solved() {
+                if queued.remove(&node_id) {
+                    pending.retain(|&(pending_id, _)| pending_id != node_id);
+                }
+            }
+
+            let path_parent_id = self.node_stack.iter().rev().nth(1).copied();
+            let is_path_dependent = self.tree[self.current_node_id].is_path_dependent;
+            let game = self.current_game();
+
+            let other_parent_edges: Vec<_> = self
+                .tree
+                .edges_directed(self.current_node_id, Direction::Incoming)
+                .filter(|edge| Some(edge.source()) != path_parent_id)
+                .map(|edge| (edge.id(), edge.source(), *edge.weight()))
+                .collect();
+
+            for (edge_id, parent_id, go_move) in other_parent_edges {
+                if is_path_dependent {
+                    self.tree.remove_edge(edge_id);
+
+                    let mut new_node = self.create_leaf(&game);
+                    new_node.last_visited = self.iteration;
+
+                    let new_node_id = self.tree.add_node(new_node);
+                    self.tree.add_edge(parent_id, new_node_id, go_move);
+                }
+
+                if queued.insert(parent_id) {
+                    pending.push((parent_id, self.current_type.flip()));
+                }
+            }
 
             if !self.move_up() {
                 break;
             }
         }
+
+        while let Some((node_id, node_type)) = pending.pop() {
+            queued.remove(&node_id);
+
+            // The parent may have been pruned further up the path.
+            if !self.tree.contains_node(node_id) {
+                continue;
+            }
+
+            if self.set_proof_and_disproof_numbers(node_id, node_type) {
+                for parent_id in self.tree.neighbors_directed(node_id, Direction::Incoming) {
+                    if queued.insert(parent_id) {
+                        pending.push((parent_id, node_type.flip()));
+                    }
+                }
+            }
+        }
+
+        while self.move_up() {}
     }
 
-    fn set_proof_and_disproof_numbers(&mut self) -> bool {
+    /// Sets the numbers of a node from those of its children,
+    /// returning whether anything changed.
+    ///
+    /// A node is path dependent if any of its children are, unless
+    /// it is solved by a child that isn't path dependent.
+    fn set_proof_and_disproof_numbers(&mut self, node_id: NodeIndex, node_type: NodeType) -> bool {
         let children = self
             .tree
-            .neighbors(self.current_node_id)
+            .neighbors(node_id)
             .map(|child_id| self.tree[child_id]);
 
+        let mut has_path_dependent_child = false;
+        let mut is_solved_independently = false;
+
         let mut proof_number_sum = ProofNumber::finite(0);
         let mut proof_number_min = ProofNumber::infinite();
         let mut disproof_number_sum = ProofNumber::finite(0);
@@ -313,58 +809,190 @@ impl<P: Profiler> Puzzle<P> {
             if child.disproof_number < disproof_number_min {
                 disproof_number_min = child.disproof_number;
             }
+
+            has_path_dependent_child |= child.is_path_dependent;
+            is_solved_independently |= !child.is_path_dependent
+                && match node_type {
+                    NodeType::And => child.is_disproved(),
+                    NodeType::Or => child.is_proved(),
+                };
         }
 
-        let node = &mut self.tree[self.current_node_id];
-        match self.current_type {
-            NodeType::And => {
-                let has_changed = proof_number_sum != node.proof_number
-                    || disproof_number_min != node.disproof_number;
+        let is_path_dependent = has_path_dependent_child && !is_solved_independently;
 
-                node.proof_number = proof_number_sum;
-                node.disproof_number = disproof_number_min;
+        let node = &mut self.tree[node_id];
+        let (proof_number, disproof_number) = match node_type {
+            NodeType::And => (proof_number_sum, disproof_number_min),
+            NodeType::Or => (proof_number_min, disproof_number_sum),
+        };
 
-                has_changed
-            }
-            NodeType::Or => {
-                let has_changed = proof_number_min != node.proof_number
-                    || disproof_number_sum != node.disproof_number;
+        let has_changed = proof_number != node.proof_number
+            || disproof_number != node.disproof_number
+            || is_path_dependent != node.is_path_dependent;
 
-                node.proof_number = proof_number_min;
-                node.disproof_number = disproof_number_sum;
+        node.proof_number = proof_number;
+        node.disproof_number = disproof_number;
+        node.is_path_dependent = is_path_dependent;
 
-                has_changed
-            }
-        }
+        has_changed
     }
 
-    fn prune_if_solved(&mut self) {
+    /// Removes the children of the current node once it is solved,
+    /// keeping any that are still shared with other parents.
+    ///
+    /// Returns the removed nodes, whose indices may be reused by new nodes.
+    fn prune_if_solved(&mut self) -> Vec<NodeIndex> {
+        let mut removed = Vec::new();
+
         // Don't prune the root
         if self.current_node_id == self.root_id {
-            return;
+            return removed;
         }
 
         let node = self.tree[self.current_node_id];
 
         if node.is_solved() {
+            let game = self.current_game();
             let mut walker = self.tree.neighbors(self.current_node_id).detach();
-            while let Some(child_id) = walker.next_node(&self.tree) {
+            while let Some((edge_id, child_id)) = walker.next(&self.tree) {
+                let is_shared = self
+                    .tree
+                    .neighbors_directed(child_id, Direction::Incoming)
+                    .nth(1)
+                    .is_some();
+
+                if is_shared {
+                    self.tree.remove_edge(edge_id);
+                    continue;
+                }
+
+                let child_game = game.play_move(self.tree[edge_id]).unwrap();
+                if self.transposition_table.get(&child_game) == Some(&child_id) {
+                    self.transposition_table.remove(&child_game);
+                }
+
                 self.tree.remove_node(child_id);
+                removed.push(child_id);
             }
         }
+
+        removed
     }
 
     fn root_node(&self) -> AndOrNode {
         self.tree[self.root_id]
     }
 
-    pub fn first_move(&self) -> Move {
-        *self
-            .tree
+    /// A move that wins for the player to move, if one has been found.
+    pub fn first_move(&self) -> Option<Move> {
+        self.tree
             .edges(self.root_id)
             .find(|edge| self.tree[edge.target()].is_proved())
-            .unwrap()
-            .weight()
+            .map(|edge| *edge.weight())
+    }
+
+    /// Searches every move from the root until it is either proved or disproved,
+    /// or until the timeout is reached, returning the status of each move.
+    ///
+    /// Unlike [`solve`](#method.solve), this keeps searching after the first winning
+    /// move is found, so that alternative solutions are not hidden.
+    pub fn classify_root_moves(&mut self, timeout: Duration) -> HashMap<Move, MoveStatus> {
+        let timeout_at = Instant::now() + timeout;
+
+        if self.tree.neighbors(self.root_id).next().is_none() {
+            self.solve_iteration();
+        }
+
+        while Instant::now() <= timeout_at {
+            // Work on the unsolved move that looks closest to being solved either way.
+            let next_edge = self
+                .tree
+                .edges(self.root_id)
+                .filter(|edge| !self.tree[edge.target()].is_solved())
+                .min_by_key(|edge| {
+                    let child = self.tree[edge.target()];
+
+                    child.proof_number.min(child.disproof_number)
+                })
+                .map(|edge| (edge.target(), *edge.weight()));
+
+            match next_edge {
+                Some((child_id, go_move)) => {
+                    self.move_down(child_id, go_move);
+                    self.solve_iteration();
+                }
+                None => break,
+            }
+        }
+
+        self.tree
+            .edges(self.root_id)
+            .map(|edge| {
+                let child = self.tree[edge.target()];
+
+                let status = if child.is_proved() {
+                    MoveStatus::Win
+                } else if child.is_disproved() {
+                    MoveStatus::Loss
+                } else {
+                    MoveStatus::Unknown
+                };
+
+                (*edge.weight(), status)
+            })
+            .collect()
+    }
+}
+
+impl<P: Profiler> Solver for Puzzle<P> {
+    fn with_goal(game: GoGame, goal: Goal) -> Puzzle<P> {
+        let player = game.current_player;
+
+        let mut tree = StableGraph::<AndOrNode, Move>::new();
+
+        let root_id = tree.add_node(AndOrNode::create_non_terminal_leaf());
+
+        let mut transposition_table = HashMap::new();
+        transposition_table.insert(game, root_id);
+
+        Puzzle {
+            player,
+            goal,
+            tree,
+            root_id,
+            current_node_id: root_id,
+            transposition_table,
+            node_stack: vec![root_id],
+            game_stack: vec![game],
+            previous_games: Vec::new(),
+            current_type: NodeType::Or,
+            iteration: 0,
+            node_limit: None,
+            leaf_evaluator: Arc::new(ConstantEvaluator),
+            prunes_irrelevant_moves: false,
+            profiler: P::new(),
+        }
+    }
+
+    fn search(&mut self, limits: SolveLimits) -> SolveResult {
+        let start = Instant::now();
+        let start_iteration = self.iteration;
+
+        let outcome = self.solve_with_limits(limits);
+
+        SolveResult {
+            outcome,
+            winning_moves: self
+                .tree
+                .edges(self.root_id)
+                .filter(|edge| self.tree[edge.target()].is_proved())
+                .map(|edge| *edge.weight())
+                .collect(),
+            stats: SolveStats {
+                expanded_nodes: (self.iteration - start_iteration) as usize,
+                elapsed: start.elapsed(),
+            },
+        }
     }
 }
 
@@ -385,9 +1013,12 @@ mod tests {
         puzzle.solve();
 
         assert!(puzzle.root_node().is_proved());
-        assert_eq!(puzzle.first_move(), Move::Place(BoardPosition::new(4, 0)));
-        assert_display_snapshot!(puzzle.profiler.node_count, @"542");
-        assert_display_snapshot!(puzzle.profiler.max_depth, @"7");
+        assert_eq!(
+            puzzle.first_move(),
+            Some(Move::Place(BoardPosition::new(4, 0)))
+        );
+        assert_display_snapshot!(puzzle.profiler.node_count, @"73");
+        assert_display_snapshot!(puzzle.profiler.max_depth, @"4");
     }
 
     #[test]
@@ -399,8 +1030,11 @@ mod tests {
         puzzle.solve();
 
         assert!(puzzle.root_node().is_proved(), "{:?}", puzzle.root_node());
-        assert_eq!(puzzle.first_move(), Move::Place(BoardPosition::new(2, 1)));
-        assert_display_snapshot!(puzzle.profiler.node_count, @"2989");
+        assert_eq!(
+            puzzle.first_move(),
+            Some(Move::Place(BoardPosition::new(2, 1)))
+        );
+        assert_display_snapshot!(puzzle.profiler.node_count, @"1527");
         assert_display_snapshot!(puzzle.profiler.max_depth, @"15");
     }
 
@@ -413,8 +1047,11 @@ mod tests {
         puzzle.solve();
 
         assert!(puzzle.root_node().is_proved(), "{:?}", puzzle.root_node());
-        assert_eq!(puzzle.first_move(), Move::Place(BoardPosition::new(5, 0)));
-        assert_display_snapshot!(puzzle.profiler.node_count, @"219");
+        assert_eq!(
+            puzzle.first_move(),
+            Some(Move::Place(BoardPosition::new(5, 0)))
+        );
+        assert_display_snapshot!(puzzle.profiler.node_count, @"145");
         assert_display_snapshot!(puzzle.profiler.max_depth, @"9");
     }
 
@@ -427,9 +1064,41 @@ mod tests {
         puzzle.solve();
 
         assert!(puzzle.root_node().is_proved(), "{:?}", puzzle.root_node());
-        assert_eq!(puzzle.first_move(), Move::Place(BoardPosition::new(7, 0)));
-        assert_display_snapshot!(puzzle.profiler.node_count, @"59140");
-        assert_display_snapshot!(puzzle.profiler.max_depth, @"18");
+        assert_eq!(
+            puzzle.first_move(),
+            Some(Move::Place(BoardPosition::new(7, 0)))
+        );
+        assert_display_snapshot!(puzzle.profiler.node_count, @"11594");
+        assert_display_snapshot!(puzzle.profiler.max_depth, @"16");
+    }
+
+    #[test]
+    fn true_simple4_white_first_avoids_seki() {
+        let tsumego = GoGame::from_sgf(include_str!("test_sgfs/puzzles/true_simple4.sgf"));
+        let tsumego = GoGame::from_board(tsumego.board, GoPlayer::White);
+
+        let mut puzzle = Puzzle::<Profile>::new(tsumego);
+
+        puzzle.solve();
+
+        assert!(puzzle.root_node().is_proved());
+        assert_eq!(
+            puzzle.first_move(),
+            Some(Move::Place(BoardPosition::new(7, 0)))
+        );
+
+        // Counting seki as life rules out the attacker's first choice,
+        // which only leaves the defender alive in seki.
+        let goal = Goal::guess(tsumego).with_seki_is_life(true);
+        let mut puzzle = Puzzle::<Profile>::with_goal(tsumego, goal);
+
+        puzzle.solve();
+
+        assert!(puzzle.root_node().is_proved());
+        assert_eq!(
+            puzzle.first_move(),
+            Some(Move::Place(BoardPosition::new(5, 0)))
+        );
     }
 
     #[test]
@@ -441,8 +1110,11 @@ mod tests {
         puzzle.solve();
 
         assert!(puzzle.root_node().is_proved(), "{:?}", puzzle.root_node());
-        assert_eq!(puzzle.first_move(), Move::Place(BoardPosition::new(14, 2)));
-        assert_display_snapshot!(puzzle.profiler.node_count, @"176977");
+        assert_eq!(
+            puzzle.first_move(),
+            Some(Move::Place(BoardPosition::new(14, 2)))
+        );
+        assert_display_snapshot!(puzzle.profiler.node_count, @"12715");
         assert_display_snapshot!(puzzle.profiler.max_depth, @"25");
     }
 
@@ -455,7 +1127,10 @@ mod tests {
         puzzle.solve();
 
         assert!(puzzle.root_node().is_proved(), "{:?}", puzzle.root_node());
-        assert_eq!(puzzle.first_move(), Move::Place(BoardPosition::new(1, 0)));
+        assert_eq!(
+            puzzle.first_move(),
+            Some(Move::Place(BoardPosition::new(1, 0)))
+        );
         assert_display_snapshot!(puzzle.profiler.node_count, @"5");
         assert_display_snapshot!(puzzle.profiler.max_depth, @"2");
     }
@@ -469,11 +1144,350 @@ mod tests {
         puzzle.solve();
 
         assert!(puzzle.root_node().is_proved(), "{:?}", puzzle.root_node());
-        assert_eq!(puzzle.first_move(), Move::Place(BoardPosition::new(1, 0)));
-        assert_display_snapshot!(puzzle.profiler.node_count, @"173");
+        assert_eq!(
+            puzzle.first_move(),
+            Some(Move::Place(BoardPosition::new(1, 0)))
+        );
+        assert_display_snapshot!(puzzle.profiler.node_count, @"109");
         assert_display_snapshot!(puzzle.profiler.max_depth, @"9");
     }
 
+    #[test]
+    fn knowledge_evaluator() {
+        let tsumego = GoGame::from_sgf(include_str!("test_sgfs/puzzles/true_simple4.sgf"));
+
+        let mut puzzle = Puzzle::<Profile>::new(tsumego);
+        puzzle.set_leaf_evaluator(KnowledgeEvaluator);
+
+        puzzle.solve();
+
+        assert!(puzzle.root_node().is_proved());
+        assert_eq!(
+            puzzle.first_move(),
+            Some(Move::Place(BoardPosition::new(7, 0)))
+        );
+        assert_display_snapshot!(puzzle.profiler.node_count, @"7675");
+    }
+
+    #[test]
+    fn relevance_zone_pruning() {
+        let tsumego = GoGame::from_sgf(include_str!("test_sgfs/puzzles/true_simple1.sgf"));
+
+        let mut puzzle = Puzzle::<Profile>::new(tsumego);
+        puzzle.set_relevance_zone_pruning(true);
+
+        puzzle.solve();
+
+        assert!(puzzle.root_node().is_proved());
+        assert_eq!(
+            puzzle.first_move(),
+            Some(Move::Place(BoardPosition::new(4, 0)))
+        );
+        assert_display_snapshot!(puzzle.profiler.node_count, @"62");
+    }
+
+    #[test]
+    fn solve_with_limits() {
+        let tsumego = GoGame::from_sgf(include_str!("test_sgfs/puzzles/true_simple2.sgf"));
+
+        let mut puzzle = Puzzle::<NoProfile>::new(tsumego);
+        assert_eq!(
+            puzzle.solve_with_limits(SolveLimits::default().with_max_expanded_nodes(10)),
+            SolveOutcome::NodeLimit
+        );
+        assert_eq!(puzzle.iteration, 10);
+
+        assert_eq!(
+            puzzle.solve_with_limits(SolveLimits::default().with_max_tree_size(20)),
+            SolveOutcome::MemoryLimit
+        );
+        assert!(puzzle.tree.node_count() > 20);
+
+        assert_eq!(
+            puzzle.solve_with_limits(SolveLimits::default().with_timeout(Duration::from_secs(0))),
+            SolveOutcome::TimedOut
+        );
+
+        assert_eq!(
+            puzzle.solve_with_limits(SolveLimits::default()),
+            SolveOutcome::Proved
+        );
+        assert_eq!(
+            Puzzle::<NoProfile>::new(
+                tsumego
+                    .play_move(Move::Place(BoardPosition::new(2, 1)))
+                    .unwrap()
+            )
+            .solve_with_limits(SolveLimits::default()),
+            SolveOutcome::Disproved
+        );
+    }
+
+    #[test]
+    fn reroot() {
+        let tsumego = GoGame::from_sgf(include_str!("test_sgfs/puzzles/true_simple2.sgf"));
+        let mut puzzle = Puzzle::<NoProfile>::new(tsumego);
+
+        for _ in 0..50 {
+            puzzle.solve_iteration();
+        }
+        assert!(!puzzle.is_solved());
+
+        let node_count = puzzle.tree.node_count();
+        let go_move = *puzzle.tree.edges(puzzle.root_id).next().unwrap().weight();
+        puzzle.reroot(go_move).unwrap();
+
+        assert!(puzzle.tree.node_count() > 1);
+        assert!(puzzle.tree.node_count() < node_count);
+        assert_eq!(puzzle.current_game(), tsumego.play_move(go_move).unwrap());
+
+        puzzle.solve();
+
+        let mut fresh = Puzzle::<NoProfile>::new(tsumego.play_move(go_move).unwrap());
+        fresh.solve();
+
+        assert_eq!(puzzle.is_proved(), fresh.is_proved());
+    }
+
+    #[test]
+    fn reroot_at_solved_child() {
+        let tsumego = GoGame::from_sgf(include_str!("test_sgfs/puzzles/true_simple1.sgf"));
+        let mut puzzle = Puzzle::<NoProfile>::new(tsumego);
+        puzzle.solve();
+
+        puzzle.reroot(puzzle.first_move().unwrap()).unwrap();
+
+        // The child's own moves were pruned once it was solved,
+        // so they have to be found again.
+        assert!(!puzzle.is_solved());
+        puzzle.solve();
+
+        // The defender lives, so the attacker to move can't win.
+        assert!(!puzzle.is_proved());
+
+        assert_eq!(
+            puzzle.reroot(Move::Place(BoardPosition::new(4, 0))),
+            Err(MoveError::Occupied)
+        );
+    }
+
+    #[test]
+    fn transpositions_share_nodes() {
+        let tsumego = GoGame::from_sgf(include_str!("test_sgfs/puzzles/true_simple2.sgf"));
+        let mut puzzle = Puzzle::<NoProfile>::new(tsumego);
+
+        puzzle.solve();
+
+        assert!(puzzle.tree.node_indices().any(|node_id| {
+            puzzle
+                .tree
+                .neighbors_directed(node_id, Direction::Incoming)
+                .count()
+                > 1
+        }));
+    }
+
+    #[test]
+    fn path_dependent_nodes_are_not_shared() {
+        let tsumego = GoGame::from_sgf(include_str!("test_sgfs/puzzles/ko_repetition1.sgf"));
+
+        for &player in GoPlayer::both() {
+            for &ko_master in GoPlayer::both() {
+                let game =
+                    GoGame::from_board(tsumego.board, player).with_ko_master(Some(ko_master));
+                let mut puzzle = Puzzle::<NoProfile>::new(game);
+
+                while !puzzle.is_solved() {
+                    puzzle.solve_iteration();
+
+                    assert!(puzzle.tree.node_indices().all(|node_id| {
+                        !puzzle.tree[node_id].is_path_dependent
+                            || puzzle
+                                .tree
+                                .neighbors_directed(node_id, Direction::Incoming)
+                                .count()
+                                <= 1
+                    }));
+                }
+
+                let mut solver = Dfpn::<NoProfile>::new(game);
+                solver.solve();
+
+                assert_eq!(puzzle.is_proved(), solver.is_proved());
+
+                puzzle.proof_tree().unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn replacement_leaves_use_leaf_evaluator() {
+        struct FixedEvaluator;
+
+        impl LeafEvaluator for FixedEvaluator {
+            fn evaluate(&self, _game: &GoGame, _attacker: GoPlayer) -> (u32, u32) {
+                (3, 5)
+            }
+        }
+
+        let tsumego = GoGame::from_sgf(include_str!("test_sgfs/puzzles/target_save1.sgf"));
+        let goal = Goal::guess(tsumego).with_targets(
+            tsumego.board,
+            tsumego.board.get_bitboard_for_player(GoPlayer::White),
+        );
+
+        // A path dependent node here stops being shared, and its other
+        // parents are given a new leaf in its place.
+        let mut puzzle = Puzzle::<NoProfile>::with_goal(tsumego, goal);
+        puzzle.set_leaf_evaluator(FixedEvaluator);
+
+        while !puzzle.is_solved() {
+            puzzle.solve_iteration();
+
+            assert!(puzzle.tree.node_indices().all(|node_id| {
+                let node = puzzle.tree[node_id];
+
+                node.is_solved()
+                    || puzzle.tree.neighbors(node_id).next().is_some()
+                    || node.proof_number + node.disproof_number == ProofNumber::finite(8)
+            }));
+        }
+    }
+
+    #[test]
+    fn pruned_nodes_are_not_updated() {
+        let tsumego = GoGame::from_sgf(include_str!("test_sgfs/puzzles/target_save1.sgf"));
+        let goal = Goal::guess(tsumego).with_targets(
+            tsumego.board,
+            tsumego.board.get_bitboard_for_player(GoPlayer::White),
+        );
+
+        // Pruning a solved node here frees an index that a new leaf takes
+        // while the pruned node is still waiting for its numbers to be updated.
+        let mut puzzle = Puzzle::<NoProfile>::with_goal(tsumego, goal);
+        puzzle.solve();
+
+        let mut solver = Dfpn::<NoProfile>::with_goal(tsumego, goal);
+        solver.solve();
+
+        assert_eq!(puzzle.is_proved(), solver.is_proved());
+    }
+
+    #[test]
+    fn solves_within_node_limit() {
+        let tsumego = GoGame::from_sgf(include_str!("test_sgfs/puzzles/true_simple4.sgf"));
+        let mut puzzle = Puzzle::<NoProfile>::new(tsumego);
+        puzzle.set_node_limit(2000);
+
+        while !puzzle.is_solved() {
+            puzzle.solve_iteration();
+
+            assert!(puzzle.tree.node_count() <= 2000);
+        }
+
+        assert!(puzzle.is_proved());
+        assert_eq!(
+            puzzle.first_move(),
+            Some(Move::Place(BoardPosition::new(7, 0)))
+        );
+    }
+
+    #[test]
+    fn solves_within_memory_limit() {
+        let tsumego = GoGame::from_sgf(include_str!("test_sgfs/puzzles/true_simple4.sgf"));
+        let mut puzzle = Puzzle::<NoProfile>::new(tsumego);
+        puzzle.set_memory_limit(200_000);
+
+        let node_limit = 200_000 / Puzzle::<NoProfile>::estimated_bytes_per_node();
+        assert_eq!(puzzle.node_limit, Some(node_limit));
+
+        while !puzzle.is_solved() {
+            puzzle.solve_iteration();
+
+            assert!(puzzle.tree.node_count() <= node_limit);
+        }
+
+        assert!(puzzle.is_proved());
+        assert_eq!(
+            puzzle.first_move(),
+            Some(Move::Place(BoardPosition::new(7, 0)))
+        );
+    }
+
+    #[test]
+    fn classify_root_moves() {
+        let tsumego = GoGame::from_sgf(include_str!("test_sgfs/puzzles/true_simple1.sgf"));
+        let mut puzzle = Puzzle::<NoProfile>::new(tsumego);
+
+        let statuses = puzzle.classify_root_moves(Duration::from_secs(60));
+
+        assert_eq!(statuses.len(), tsumego.generate_moves().len());
+        assert_eq!(
+            statuses[&Move::Place(BoardPosition::new(4, 0))],
+            MoveStatus::Win
+        );
+        assert!(statuses
+            .values()
+            .all(|&status| status != MoveStatus::Unknown));
+        assert!(puzzle.is_proved());
+    }
+
+    #[test]
+    fn first_move_of_disproved_puzzle() {
+        let tsumego = GoGame::from_sgf(include_str!("test_sgfs/puzzles/true_ultrasimple1.sgf"));
+        let tsumego = tsumego
+            .play_move(Move::Place(BoardPosition::new(1, 0)))
+            .unwrap();
+        let mut puzzle = Puzzle::<NoProfile>::new(tsumego);
+
+        puzzle.solve();
+
+        assert!(!puzzle.is_proved());
+        assert_eq!(puzzle.first_move(), None);
+    }
+
+    #[test]
+    fn solve_with_progress() {
+        let tsumego = GoGame::from_sgf(include_str!("test_sgfs/puzzles/true_simple1.sgf"));
+        let mut puzzle = Puzzle::<NoProfile>::new(tsumego);
+
+        let mut reports = Vec::new();
+        let solved = puzzle.solve_with_progress(
+            &CancellationToken::new(),
+            Duration::from_secs(0),
+            |progress| reports.push(progress),
+        );
+
+        assert!(solved);
+        assert!(puzzle.is_proved());
+        assert_eq!(reports[0].node_count, 1);
+
+        let last = reports.last().unwrap();
+        assert_eq!(last.proof_number, ProofNumber::finite(0));
+        assert_eq!(last.node_count, puzzle.tree.node_count());
+    }
+
+    #[test]
+    fn cancel_solve() {
+        let tsumego = GoGame::from_sgf(include_str!("test_sgfs/puzzles/true_simple1.sgf"));
+        let mut puzzle = Puzzle::<NoProfile>::new(tsumego);
+
+        let cancellation = CancellationToken::new();
+        let mut report_count = 0;
+        let solved =
+            puzzle.solve_with_progress(&cancellation.clone(), Duration::from_secs(0), |_| {
+                report_count += 1;
+
+                if report_count == 3 {
+                    cancellation.cancel();
+                }
+            });
+
+        assert!(!solved);
+        assert!(!puzzle.is_solved());
+        assert_eq!(report_count, 4);
+    }
+
     #[test]
     fn trace_expanded_nodes() {
         let tsumego = GoGame::from_sgf(include_str!("test_sgfs/puzzles/true_ultrasimple2.sgf"));
@@ -482,10 +1496,8 @@ mod tests {
         puzzle.solve();
 
         let mut output = String::new();
-        let mut count = 1;
-        for (node, depth) in puzzle.profiler.expanded_list {
+        for (count, (node, depth)) in (1..).zip(puzzle.profiler.expanded_list) {
             output.push_str(format!("{}, depth {}:\n{}\n\n", count, depth, node.board).borrow());
-            count += 1;
         }
 
         assert_snapshot!(output);
@@ -0,0 +1,315 @@
+//! An exhaustive alpha-beta search, used as a reference to check the other solvers.
+//!
+//! Every line is searched to the end, and nothing is stored between positions,
+//! so all it shares with the other solvers is the move generator and the
+//! terminal rules. This makes it far too slow for anything but tiny positions.
+
+use super::solve_limits::{SolveLimits, SolveOutcome};
+use super::solver::{Goal, SolveResult, SolveStats, Solver};
+use super::{terminal_detection, Profiler};
+use crate::go::{GoGame, Move};
+use std::time::{Duration, Instant};
+
+pub struct AlphaBeta<P: Profiler> {
+    /// What each player is trying to achieve.
+    goal: Goal,
+    game: GoGame,
+    /// The positions on the path from the root to the node being searched.
+    path: Vec<GoGame>,
+    /// Whether the player to move at the root wins, once known.
+    result: Option<bool>,
+    first_move: Option<Move>,
+    expanded_count: usize,
+    limits: SolveLimits,
+    timeout_at: Option<Instant>,
+    /// Why the search was stopped early, if it was.
+    stopped: Option<SolveOutcome>,
+    pub profiler: P,
+}
+
+impl<P: Profiler> AlphaBeta<P> {
+    pub fn new(game: GoGame) -> AlphaBeta<P> {
+        Self::with_goal(game, Goal::guess(game))
+    }
+
+    pub fn from_sgf(sgf_string: &str) -> AlphaBeta<P> {
+        Self::new(GoGame::from_sgf(sgf_string))
+    }
+
+    pub fn solve(&mut self) {
+        self.solve_with_limits(SolveLimits::default());
+    }
+
+    pub fn solve_with_timeout(&mut self, timeout: Duration) -> bool {
+        self.solve_with_limits(SolveLimits::default().with_timeout(timeout))
+            .is_solved()
+    }
+
+    /// Searches until the puzzle is solved or one of the limits is reached.
+    ///
+    /// Only the positions on the current path are kept in memory,
+    /// so the tree size limit is never reached. A search that's
+    /// stopped early starts again from the beginning.
+    pub fn solve_with_limits(&mut self, limits: SolveLimits) -> SolveOutcome {
+        self.expanded_count = 0;
+
+        if self.result.is_none() {
+            self.limits = limits;
+            self.timeout_at = limits.timeout.map(|timeout| Instant::now() + timeout);
+            self.stopped = None;
+
+            let wins = self.player_to_move_wins(self.game);
+
+            if let Some(outcome) = self.stopped {
+                return outcome;
+            }
+
+            self.result = Some(wins);
+        }
+
+        if self.is_proved() {
+            SolveOutcome::Proved
+        } else {
+            SolveOutcome::Disproved
+        }
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.result.is_some()
+    }
+
+    pub fn is_proved(&self) -> bool {
+        self.result == Some(true)
+    }
+
+    /// A move that wins for the player to move, if one has been found.
+    pub fn first_move(&self) -> Option<Move> {
+        if self.is_proved() {
+            self.first_move
+        } else {
+            None
+        }
+    }
+
+    /// Counts a node, returning false if the search has to stop.
+    fn enter(&mut self, game: GoGame) -> bool {
+        self.expanded_count += 1;
+
+        if self
+            .limits
+            .max_expanded_nodes
+            .is_some_and(|max_expanded_nodes| self.expanded_count > max_expanded_nodes)
+        {
+            self.stopped = Some(SolveOutcome::NodeLimit);
+        } else if self
+            .timeout_at
+            .is_some_and(|timeout_at| Instant::now() > timeout_at)
+        {
+            self.stopped = Some(SolveOutcome::TimedOut);
+        }
+
+        if self.stopped.is_some() {
+            return false;
+        }
+
+        self.path.push(game);
+        self.profiler.move_down();
+
+        true
+    }
+
+    fn leave(&mut self) {
+        self.path.pop();
+        self.profiler.move_up();
+    }
+
+    /// Whether the player to move in `game` wins.
+    ///
+    /// The values are only wins and losses, so alpha-beta comes down to
+    /// stopping at the first winning move.
+    fn player_to_move_wins(&mut self, game: GoGame) -> bool {
+        // The root is searched even if it's terminal, to find a first move.
+        let is_root = self.path.is_empty();
+
+        if !is_root {
+            if let Some(status) =
+                terminal_detection::is_terminal(game, self.goal.attacker, self.goal.targets)
+            {
+                return status.winner(self.goal.attacker, self.goal.seki_is_life)
+                    == game.current_player;
+            }
+        }
+
+        if !self.enter(game) {
+            return false;
+        }
+
+        // A move back to a position on the current path is treated as illegal,
+        // as it would be under a positional superko rule.
+        let moves: Vec<_> = game
+            .generate_moves()
+            .into_iter()
+            .filter(|(child, _)| !self.path.contains(child))
+            .collect();
+
+        self.profiler.expand_node(game, moves.len() as u8);
+
+        let mut wins = false;
+
+        for (child, go_move) in moves {
+            let child_wins = self.player_to_move_wins(child);
+
+            if self.stopped.is_some() {
+                break;
+            }
+
+            if !child_wins {
+                if is_root {
+                    self.first_move = Some(go_move);
+                }
+
+                wins = true;
+                break;
+            }
+        }
+
+        self.leave();
+
+        wins
+    }
+}
+
+impl<P: Profiler> Solver for AlphaBeta<P> {
+    fn with_goal(game: GoGame, goal: Goal) -> AlphaBeta<P> {
+        AlphaBeta {
+            goal,
+            game,
+            path: Vec::new(),
+            result: None,
+            first_move: None,
+            expanded_count: 0,
+            limits: SolveLimits::default(),
+            timeout_at: None,
+            stopped: None,
+            profiler: P::new(),
+        }
+    }
+
+    fn search(&mut self, limits: SolveLimits) -> SolveResult {
+        let start = Instant::now();
+
+        let outcome = self.solve_with_limits(limits);
+
+        SolveResult {
+            outcome,
+            winning_moves: self.first_move().into_iter().collect(),
+            stats: SolveStats {
+                expanded_nodes: self.expanded_count,
+                elapsed: start.elapsed(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generation;
+    use crate::go::{BoardCell, BoardPosition, GoBoard, GoPlayer};
+    use crate::puzzle::{NoProfile, Puzzle};
+    use quickcheck::{Arbitrary, Gen, TestResult};
+    use quickcheck_macros::quickcheck;
+    use std::fmt;
+    use std::fmt::{Debug, Formatter};
+
+    #[test]
+    fn true_ultrasimple1() {
+        let mut solver = AlphaBeta::<NoProfile>::from_sgf(include_str!(
+            "../test_sgfs/puzzles/true_ultrasimple1.sgf"
+        ));
+
+        solver.solve();
+
+        assert!(solver.is_proved());
+        assert_eq!(
+            solver.first_move(),
+            Some(Move::Place(BoardPosition::new(1, 0)))
+        );
+    }
+
+    /// The most empty points a position may have for every line to be searched.
+    const MAX_EMPTY_POINTS: u32 = 5;
+
+    /// A candidate puzzle small enough to search exhaustively.
+    #[derive(Clone)]
+    struct SmallPosition(GoBoard);
+
+    impl Arbitrary for SmallPosition {
+        fn arbitrary<G: Gen>(g: &mut G) -> SmallPosition {
+            loop {
+                let board = generation::generate_candidate(g).board();
+
+                let empty_points = board.empty_cells() & !board.out_of_bounds();
+
+                if empty_points.count() <= MAX_EMPTY_POINTS && !board.has_dead_groups() {
+                    return SmallPosition(board);
+                }
+            }
+        }
+
+        /// Shrinks by taking away one stone at a time.
+        fn shrink(&self) -> Box<dyn Iterator<Item = SmallPosition>> {
+            let board = self.0;
+
+            let stones = board.get_bitboard_for_player(GoPlayer::Black)
+                | board.get_bitboard_for_player(GoPlayer::White);
+
+            Box::new(
+                stones
+                    .positions()
+                    .map(move |position| {
+                        let mut board = board;
+                        board.set_cell(position, BoardCell::Empty);
+
+                        SmallPosition(board)
+                    })
+                    .filter(|position| !position.0.has_dead_groups()),
+            )
+        }
+    }
+
+    /// Shows the position as an SGF, so a failure can be reproduced from it.
+    impl Debug for SmallPosition {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.0.to_sgf())
+        }
+    }
+
+    #[quickcheck]
+    fn agrees_with_puzzle(position: SmallPosition) -> TestResult {
+        for &player in GoPlayer::both() {
+            let game = GoGame::from_board(position.0, player);
+
+            let mut reference = AlphaBeta::<NoProfile>::new(game);
+            let limits = SolveLimits::default().with_max_expanded_nodes(20_000);
+
+            if !reference.solve_with_limits(limits).is_solved() {
+                return TestResult::discard();
+            }
+
+            let mut puzzle = Puzzle::<NoProfile>::new(game);
+            puzzle.solve();
+
+            if puzzle.is_proved() != reference.is_proved() {
+                return TestResult::error(format!(
+                    "with {:?} to move, the reference solver says {} but the puzzle says {}",
+                    player,
+                    reference.is_proved(),
+                    puzzle.is_proved(),
+                ));
+            }
+        }
+
+        TestResult::passed()
+    }
+}
@@ -0,0 +1,444 @@
+//! Saving the search state of a [`Puzzle`] so that the search can be resumed later.
+//!
+//! The format is a small binary one of our own. It starts with a magic number
+//! and a format version, which must be bumped whenever the layout changes so
+//! that old checkpoints are rejected rather than misread.
+//!
+//! Node indices are kept as they are, along with the order in which vacant
+//! slots will be reused and the order in which edges were added, which decides
+//! the order children are visited in. A loaded puzzle therefore carries on
+//! exactly as the saved one would have.
+
+use super::proof_number::ProofNumber;
+use super::{AndOrNode, Goal, NodeType, Profiler, Puzzle, Solver};
+use crate::go::{BitBoard, BoardPosition, GoGame, GoPlayer, Move};
+use petgraph::stable_graph::{EdgeIndex, NodeIndex, StableGraph};
+use petgraph::visit::{EdgeRef, NodeIndexable};
+use petgraph::Direction;
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::io::{Read, Write};
+
+const MAGIC: &[u8; 4] = b"TSPZ";
+
+/// Bump this whenever the layout below changes.
+const FORMAT_VERSION: u32 = 3;
+
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(io::Error),
+    /// The data doesn't start with the checkpoint magic number.
+    NotACheckpoint,
+    /// The checkpoint was written by a different version of the format.
+    UnsupportedVersion(u32),
+    /// The checkpoint has the right version but isn't self-consistent.
+    Corrupt,
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckpointError::Io(error) => write!(f, "failed to read checkpoint: {}", error),
+            CheckpointError::NotACheckpoint => f.write_str("not a puzzle checkpoint"),
+            CheckpointError::UnsupportedVersion(version) => write!(
+                f,
+                "checkpoint format version {} is not supported, expected {}",
+                version, FORMAT_VERSION
+            ),
+            CheckpointError::Corrupt => f.write_str("checkpoint is corrupt"),
+        }
+    }
+}
+
+impl Error for CheckpointError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CheckpointError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CheckpointError {
+    fn from(error: io::Error) -> Self {
+        CheckpointError::Io(error)
+    }
+}
+
+impl<P: Profiler> Puzzle<P> {
+    /// Writes the search state to `writer`, so that the search can be resumed
+    /// with [`load_checkpoint`](#method.load_checkpoint).
+    ///
+    /// The leaf evaluator and the profiler aren't saved, and neither is the
+    /// position reached with [`move_down`](#method.move_down).
+    pub fn save_checkpoint<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        write_u32(&mut writer, FORMAT_VERSION)?;
+
+        writer.write_all(&[
+            self.player.to_byte(),
+            self.goal.attacker.to_byte(),
+            self.goal.seki_is_life as u8,
+            self.prunes_irrelevant_moves as u8,
+        ])?;
+        writer.write_all(&self.goal.targets.to_uint().to_le_bytes())?;
+        write_u32(&mut writer, self.iteration)?;
+        write_u64(
+            &mut writer,
+            self.node_limit.map_or(u64::MAX, |limit| limit as u64),
+        )?;
+
+        writer.write_all(&self.game_stack[0].to_bytes())?;
+        write_u32(&mut writer, self.previous_games.len() as u32)?;
+        for game in self.previous_games.iter() {
+            writer.write_all(&game.to_bytes())?;
+        }
+
+        let node_bound = self.tree.node_bound();
+        write_u32(&mut writer, node_bound as u32)?;
+        write_u32(&mut writer, self.root_id.index() as u32)?;
+
+        let vacant_slots = self.vacant_slots_in_reuse_order();
+        write_u32(&mut writer, vacant_slots.len() as u32)?;
+        for node_id in vacant_slots {
+            write_u32(&mut writer, node_id.index() as u32)?;
+        }
+
+        for node_id in self.tree.node_indices() {
+            let node = self.tree[node_id];
+
+            write_u32(&mut writer, node.proof_number.to_bits())?;
+            write_u32(&mut writer, node.disproof_number.to_bits())?;
+            write_u32(&mut writer, node.last_visited)?;
+            writer.write_all(&[node.is_path_dependent as u8])?;
+        }
+
+        let edges = self.edges_in_insertion_order();
+        write_u32(&mut writer, edges.len() as u32)?;
+        for edge_id in edges {
+            let (source, target) = self.tree.edge_endpoints(edge_id).unwrap();
+
+            write_u32(&mut writer, source.index() as u32)?;
+            write_u32(&mut writer, target.index() as u32)?;
+            writer.write_all(&match self.tree[edge_id] {
+                Move::Pass => [0, 0, 0],
+                Move::Place(position) => {
+                    let (column, row) = position.to_pair();
+                    [1, column, row]
+                }
+            })?;
+        }
+
+        write_u32(&mut writer, self.transposition_table.len() as u32)?;
+        for (game, node_id) in self.transposition_table.iter() {
+            writer.write_all(&game.to_bytes())?;
+            write_u32(&mut writer, node_id.index() as u32)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a puzzle written by [`save_checkpoint`](#method.save_checkpoint),
+    /// ready for the search to carry on where it stopped.
+    ///
+    /// The leaf evaluator is reset to the default, so it must be set again
+    /// for the search to carry on exactly as before.
+    pub fn load_checkpoint<R: Read>(mut reader: R) -> Result<Puzzle<P>, CheckpointError> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(CheckpointError::NotACheckpoint);
+        }
+
+        let version = read_u32(&mut reader)?;
+        if version != FORMAT_VERSION {
+            return Err(CheckpointError::UnsupportedVersion(version));
+        }
+
+        let mut flags = [0; 4];
+        reader.read_exact(&mut flags)?;
+        let player = GoPlayer::from_byte(flags[0]).ok_or(CheckpointError::Corrupt)?;
+        let attacker = GoPlayer::from_byte(flags[1]).ok_or(CheckpointError::Corrupt)?;
+        let seki_is_life = read_bool(flags[2])?;
+        let prunes_irrelevant_moves = read_bool(flags[3])?;
+
+        let mut targets = [0; 16];
+        reader.read_exact(&mut targets)?;
+        let targets = BitBoard::from_uint(u128::from_le_bytes(targets));
+
+        let iteration = read_u32(&mut reader)?;
+        let node_limit = match read_u64(&mut reader)? {
+            u64::MAX => None,
+            limit => Some(limit as usize),
+        };
+
+        let root_game = read_game(&mut reader)?;
+        let previous_games = (0..read_u32(&mut reader)?)
+            .map(|_| read_game(&mut reader))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let node_bound = read_u32(&mut reader)? as usize;
+        let root_id = NodeIndex::new(read_u32(&mut reader)? as usize);
+
+        let vacant_slots = (0..read_u32(&mut reader)?)
+            .map(|_| read_node_id(&mut reader, node_bound))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut is_vacant = vec![false; node_bound];
+        for node_id in vacant_slots.iter() {
+            if is_vacant[node_id.index()] {
+                return Err(CheckpointError::Corrupt);
+            }
+            is_vacant[node_id.index()] = true;
+        }
+
+        let mut tree = StableGraph::<AndOrNode, Move>::with_capacity(node_bound, 0);
+        for &vacant in is_vacant.iter() {
+            let node = if vacant {
+                AndOrNode::create_non_terminal_leaf()
+            } else {
+                AndOrNode {
+                    proof_number: ProofNumber::from_bits(read_u32(&mut reader)?),
+                    disproof_number: ProofNumber::from_bits(read_u32(&mut reader)?),
+                    last_visited: read_u32(&mut reader)?,
+                    is_path_dependent: read_bool(read_u8(&mut reader)?)?,
+                }
+            };
+
+            tree.add_node(node);
+        }
+
+        // Removing the vacant slots in reverse puts the first one
+        // to be reused at the front of the free list.
+        for &node_id in vacant_slots.iter().rev() {
+            tree.remove_node(node_id);
+        }
+
+        if !tree.contains_node(root_id) {
+            return Err(CheckpointError::Corrupt);
+        }
+
+        for _ in 0..read_u32(&mut reader)? {
+            let source = read_node_id(&mut reader, node_bound)?;
+            let target = read_node_id(&mut reader, node_bound)?;
+
+            let mut bytes = [0; 3];
+            reader.read_exact(&mut bytes)?;
+            let go_move = match bytes {
+                [0, 0, 0] => Move::Pass,
+                [1, column, row] if column < 16 && row < 8 => {
+                    Move::Place(BoardPosition::new(column, row))
+                }
+                _ => return Err(CheckpointError::Corrupt),
+            };
+
+            if !tree.contains_node(source) || !tree.contains_node(target) {
+                return Err(CheckpointError::Corrupt);
+            }
+
+            tree.add_edge(source, target, go_move);
+        }
+
+        let mut transposition_table = HashMap::new();
+        for _ in 0..read_u32(&mut reader)? {
+            let game = read_game(&mut reader)?;
+            let node_id = read_node_id(&mut reader, node_bound)?;
+
+            if !tree.contains_node(node_id) {
+                return Err(CheckpointError::Corrupt);
+            }
+
+            transposition_table.insert(game, node_id);
+        }
+
+        let goal = Goal {
+            attacker,
+            seki_is_life,
+            targets,
+        };
+
+        let mut puzzle = Puzzle::with_goal(root_game, goal);
+        puzzle.player = player;
+        puzzle.tree = tree;
+        puzzle.root_id = root_id;
+        puzzle.current_node_id = root_id;
+        puzzle.transposition_table = transposition_table;
+        puzzle.node_stack = vec![root_id];
+        puzzle.current_type = NodeType::Or;
+        puzzle.previous_games = previous_games;
+        puzzle.iteration = iteration;
+        puzzle.node_limit = node_limit;
+        puzzle.prunes_irrelevant_moves = prunes_irrelevant_moves;
+
+        Ok(puzzle)
+    }
+
+    /// The vacant node slots, in the order that new nodes will be put in them.
+    fn vacant_slots_in_reuse_order(&self) -> Vec<NodeIndex> {
+        let mut probe = self.tree.map(|_, _| (), |_, _| ());
+
+        (self.tree.node_count()..self.tree.node_bound())
+            .map(|_| probe.add_node(()))
+            .collect()
+    }
+
+    /// The edges, in an order that adding them in gives each node's incoming and
+    /// outgoing edges in the same order as now.
+    ///
+    /// New edges go to the front of both lists they're in, so each list gives
+    /// the order in which its own edges were added. Any order consistent with
+    /// all of the lists will do, which this finds by a topological sort.
+    fn edges_in_insertion_order(&self) -> Vec<EdgeIndex> {
+        let mut edges_before = HashMap::<EdgeIndex, usize>::new();
+        let mut edges_after = HashMap::<EdgeIndex, Vec<EdgeIndex>>::new();
+
+        for node_id in self.tree.node_indices() {
+            for &direction in [Direction::Outgoing, Direction::Incoming].iter() {
+                let edges: Vec<_> = self
+                    .tree
+                    .edges_directed(node_id, direction)
+                    .map(|edge| edge.id())
+                    .collect();
+
+                for pair in edges.windows(2) {
+                    // The first edge of each pair was added after the second.
+                    edges_after.entry(pair[1]).or_default().push(pair[0]);
+                    *edges_before.entry(pair[0]).or_default() += 1;
+                }
+            }
+        }
+
+        let mut ready: VecDeque<_> = self
+            .tree
+            .edge_indices()
+            .filter(|edge_id| !edges_before.contains_key(edge_id))
+            .collect();
+        let mut ordered = Vec::with_capacity(self.tree.edge_count());
+
+        while let Some(edge_id) = ready.pop_front() {
+            ordered.push(edge_id);
+
+            for next_id in edges_after.remove(&edge_id).unwrap_or_default() {
+                let count = edges_before.get_mut(&next_id).unwrap();
+                *count -= 1;
+
+                if *count == 0 {
+                    ready.push_back(next_id);
+                }
+            }
+        }
+
+        debug_assert_eq!(ordered.len(), self.tree.edge_count());
+
+        ordered
+    }
+}
+
+fn write_u32<W: Write>(writer: &mut W, value: u32) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn write_u64<W: Write>(writer: &mut W, value: u64) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> io::Result<u8> {
+    let mut bytes = [0; 1];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(bytes[0])
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_bool(byte: u8) -> Result<bool, CheckpointError> {
+    match byte {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(CheckpointError::Corrupt),
+    }
+}
+
+fn read_game<R: Read>(reader: &mut R) -> Result<GoGame, CheckpointError> {
+    let mut bytes = [0; GoGame::ENCODED_LENGTH];
+    reader.read_exact(&mut bytes)?;
+
+    GoGame::from_bytes(&bytes).ok_or(CheckpointError::Corrupt)
+}
+
+fn read_node_id<R: Read>(reader: &mut R, node_bound: usize) -> Result<NodeIndex, CheckpointError> {
+    let index = read_u32(reader)? as usize;
+
+    if index < node_bound {
+        Ok(NodeIndex::new(index))
+    } else {
+        Err(CheckpointError::Corrupt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::NoProfile;
+    use super::*;
+
+    #[test]
+    fn resume_solve() {
+        let tsumego = GoGame::from_sgf(include_str!("../test_sgfs/puzzles/true_simple4.sgf"));
+
+        let mut uninterrupted = Puzzle::<NoProfile>::new(tsumego);
+        uninterrupted.set_node_limit(1000);
+        uninterrupted.solve();
+
+        let mut puzzle = Puzzle::<NoProfile>::new(tsumego);
+        puzzle.set_node_limit(1000);
+        for _ in 0..2000 {
+            puzzle.solve_iteration();
+        }
+        assert!(!puzzle.is_solved());
+
+        let mut checkpoint = Vec::new();
+        puzzle.save_checkpoint(&mut checkpoint).unwrap();
+
+        let mut resumed = Puzzle::<NoProfile>::load_checkpoint(checkpoint.as_slice()).unwrap();
+        resumed.solve();
+
+        assert!(resumed.is_proved());
+        assert_eq!(resumed.first_move(), uninterrupted.first_move());
+        assert_eq!(resumed.iteration, uninterrupted.iteration);
+        assert_eq!(resumed.tree.node_count(), uninterrupted.tree.node_count());
+    }
+
+    #[test]
+    fn reject_other_versions() {
+        let tsumego = GoGame::from_sgf(include_str!("../test_sgfs/puzzles/true_simple1.sgf"));
+
+        let mut checkpoint = Vec::new();
+        Puzzle::<NoProfile>::new(tsumego)
+            .save_checkpoint(&mut checkpoint)
+            .unwrap();
+        checkpoint[4..8].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+
+        assert!(matches!(
+            Puzzle::<NoProfile>::load_checkpoint(checkpoint.as_slice()),
+            Err(CheckpointError::UnsupportedVersion(version)) if version == FORMAT_VERSION + 1
+        ));
+        assert!(matches!(
+            Puzzle::<NoProfile>::load_checkpoint(&b"not a checkpoint"[..]),
+            Err(CheckpointError::NotACheckpoint)
+        ));
+    }
+}
@@ -0,0 +1,612 @@
+//! A depth-first proof-number (df-pn) solver.
+//!
+//! Unlike [`Puzzle`](../struct.Puzzle.html), this doesn't keep the search tree in
+//! memory. Instead, proof and disproof numbers of previously searched positions are
+//! kept in a fixed-size transposition table, so memory use stays bounded no matter
+//! how long the search runs.
+//!
+//! Values are kept in the φ/δ form: φ is the proof number from the point of view of
+//! the player to move, and δ is the disproof number.
+
+use super::proof_number::ProofNumber;
+use super::solve_limits::{SolveLimits, SolveOutcome};
+use super::solver::{Goal, SolveResult, SolveStats, Solver};
+use super::{relevance_zone, terminal_detection, Profiler};
+use crate::go::{GoGame, Move};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+pub(super) const DEFAULT_TABLE_SIZE: usize = 1 << 18;
+
+#[derive(Clone, Copy)]
+struct Entry {
+    game: GoGame,
+    phi: ProofNumber,
+    delta: ProofNumber,
+    /// The number of nodes expanded while searching this position,
+    /// used to decide which entries are worth keeping.
+    work: u64,
+}
+
+struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+}
+
+impl TranspositionTable {
+    fn new(size: usize) -> TranspositionTable {
+        TranspositionTable {
+            entries: vec![None; size.max(1)],
+        }
+    }
+
+    fn index(&self, game: &GoGame) -> usize {
+        let mut hasher = DefaultHasher::new();
+        game.hash(&mut hasher);
+
+        (hasher.finish() % self.entries.len() as u64) as usize
+    }
+
+    fn lookup(&self, game: &GoGame) -> Option<(ProofNumber, ProofNumber)> {
+        match self.entries[self.index(game)] {
+            Some(entry) if entry.game == *game => Some((entry.phi, entry.delta)),
+            _ => None,
+        }
+    }
+
+    /// Stores an entry, replacing whatever was in its slot unless the
+    /// existing entry is for a different position and took more work.
+    fn store(&mut self, entry: Entry) {
+        let index = self.index(&entry.game);
+        let slot = &mut self.entries[index];
+
+        let should_replace = match slot {
+            None => true,
+            Some(existing) => existing.game == entry.game || existing.work <= entry.work,
+        };
+
+        if should_replace {
+            *slot = Some(entry);
+        }
+    }
+}
+
+/// A transposition table shared by several threads, split into
+/// separately locked shards so that threads rarely wait for each other.
+pub(super) struct SharedTable {
+    shards: Vec<Mutex<Shard>>,
+    /// Set once any thread has solved the root, so that the others stop.
+    is_finished: AtomicBool,
+    /// The number of nodes expanded by every thread together.
+    expanded_count: AtomicUsize,
+    /// The expanded count at which every thread stops, if limited.
+    expanded_count_limit: Option<usize>,
+}
+
+struct Shard {
+    table: TranspositionTable,
+    /// The number of threads currently searching each position.
+    searching: HashMap<GoGame, u32>,
+}
+
+impl SharedTable {
+    /// Creates a table holding at most `size` entries in total, which stops the
+    /// threads using it once they've expanded `expanded_count_limit` nodes together.
+    pub(super) fn new(
+        size: usize,
+        shard_count: usize,
+        expanded_count_limit: Option<usize>,
+    ) -> SharedTable {
+        let shard_count = shard_count.max(1);
+
+        SharedTable {
+            shards: (0..shard_count)
+                .map(|_| {
+                    Mutex::new(Shard {
+                        table: TranspositionTable::new(size / shard_count),
+                        searching: HashMap::new(),
+                    })
+                })
+                .collect(),
+            is_finished: AtomicBool::new(false),
+            expanded_count: AtomicUsize::new(0),
+            expanded_count_limit,
+        }
+    }
+
+    fn shard(&self, game: &GoGame) -> MutexGuard<'_, Shard> {
+        let mut hasher = DefaultHasher::new();
+        game.hash(&mut hasher);
+
+        // The low bits pick the slot within the shard, so use the high bits here.
+        let index = (hasher.finish() >> 32) as usize % self.shards.len();
+
+        self.shards[index].lock().unwrap()
+    }
+
+    pub(super) fn lookup(&self, game: &GoGame) -> Option<(ProofNumber, ProofNumber)> {
+        self.shard(game).table.lookup(game)
+    }
+
+    /// The number of threads currently searching a position.
+    fn searching(&self, game: &GoGame) -> u32 {
+        self.shard(game).searching.get(game).copied().unwrap_or(0)
+    }
+
+    /// Marks a position as being searched by one more thread,
+    /// counting it as an expanded node.
+    fn enter(&self, game: &GoGame) {
+        *self.shard(game).searching.entry(*game).or_insert(0) += 1;
+        self.expanded_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn leave(&self, game: &GoGame) {
+        let mut shard = self.shard(game);
+
+        if let Some(searching) = shard.searching.get_mut(game) {
+            *searching -= 1;
+
+            if *searching == 0 {
+                shard.searching.remove(game);
+            }
+        }
+    }
+
+    pub(super) fn finish(&self) {
+        self.is_finished.store(true, Ordering::Relaxed);
+    }
+
+    fn is_finished(&self) -> bool {
+        self.is_finished.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn expanded_count(&self) -> usize {
+        self.expanded_count.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn has_reached_expanded_count_limit(&self) -> bool {
+        self.expanded_count_limit
+            .is_some_and(|expanded_count_limit| self.expanded_count() >= expanded_count_limit)
+    }
+}
+
+/// The transposition table used by a solver, which is
+/// either its own or shared with other threads.
+enum Table {
+    Local(TranspositionTable),
+    Shared(Arc<SharedTable>),
+}
+
+impl Table {
+    fn lookup(&self, game: &GoGame) -> Option<(ProofNumber, ProofNumber)> {
+        match self {
+            Table::Local(table) => table.lookup(game),
+            Table::Shared(table) => table.lookup(game),
+        }
+    }
+
+    fn store(&mut self, entry: Entry) {
+        match self {
+            Table::Local(table) => table.store(entry),
+            Table::Shared(table) => table.shard(&entry.game).table.store(entry),
+        }
+    }
+
+    /// The disproof number to choose `child` by. With a shared table, it's
+    /// raised by one for each thread searching the child, so that threads
+    /// prefer different moves. These virtual proof numbers are only used to
+    /// choose between children, and never for solved children.
+    fn selection_delta(&self, child: &Child) -> ProofNumber {
+        match self {
+            Table::Shared(table)
+                if child.phi != ProofNumber::finite(0) && child.delta != ProofNumber::finite(0) =>
+            {
+                child.delta + ProofNumber::finite(table.searching(&child.game))
+            }
+            _ => child.delta,
+        }
+    }
+}
+
+struct Child {
+    game: GoGame,
+    go_move: Move,
+    phi: ProofNumber,
+    delta: ProofNumber,
+    /// Whether the values depend on the path taken to reach the child.
+    is_path_dependent: bool,
+}
+
+pub struct Dfpn<P: Profiler> {
+    /// What each player is trying to achieve.
+    goal: Goal,
+    game: GoGame,
+    table: Table,
+    /// The positions on the path from the root to the node being searched.
+    path: Vec<GoGame>,
+    root_phi: ProofNumber,
+    root_delta: ProofNumber,
+    root_children: Vec<(Move, ProofNumber)>,
+    expanded_count: u64,
+    /// Whether moves outside the relevance zone are left out of the search.
+    prunes_irrelevant_moves: bool,
+    timeout_at: Option<Instant>,
+    /// The expanded count at which the search stops, if limited.
+    expanded_count_limit: Option<u64>,
+    /// Whether the search was stopped early, either by one of the limits
+    /// or by another thread sharing the table solving the puzzle.
+    is_stopped: bool,
+    pub profiler: P,
+}
+
+impl<P: Profiler> Dfpn<P> {
+    pub fn new(game: GoGame) -> Dfpn<P> {
+        Self::with_table_size(game, DEFAULT_TABLE_SIZE)
+    }
+
+    /// Creates a solver whose transposition table holds at most `table_size` entries.
+    pub fn with_table_size(game: GoGame, table_size: usize) -> Dfpn<P> {
+        Self::with_table(
+            game,
+            Goal::guess(game),
+            Table::Local(TranspositionTable::new(table_size)),
+        )
+    }
+
+    /// Creates a solver that shares `table` with solvers on other threads.
+    pub(super) fn with_shared_table(game: GoGame, goal: Goal, table: Arc<SharedTable>) -> Dfpn<P> {
+        Self::with_table(game, goal, Table::Shared(table))
+    }
+
+    fn with_table(game: GoGame, goal: Goal, table: Table) -> Dfpn<P> {
+        Dfpn {
+            goal,
+            game,
+            table,
+            path: Vec::new(),
+            root_phi: ProofNumber::finite(1),
+            root_delta: ProofNumber::finite(1),
+            root_children: Vec::new(),
+            expanded_count: 0,
+            prunes_irrelevant_moves: false,
+            timeout_at: None,
+            expanded_count_limit: None,
+            is_stopped: false,
+            profiler: P::new(),
+        }
+    }
+
+    pub fn from_sgf(sgf_string: &str) -> Dfpn<P> {
+        Self::new(GoGame::from_sgf(sgf_string))
+    }
+
+    /// Sets whether to leave out moves that can't change the result.
+    /// See [`Puzzle::set_relevance_zone_pruning`](../struct.Puzzle.html#method.set_relevance_zone_pruning).
+    pub fn set_relevance_zone_pruning(&mut self, prunes_irrelevant_moves: bool) {
+        self.prunes_irrelevant_moves = prunes_irrelevant_moves;
+    }
+
+    pub fn solve(&mut self) {
+        self.solve_with_limits(SolveLimits::default());
+    }
+
+    pub fn solve_with_timeout(&mut self, timeout: Duration) -> bool {
+        self.solve_with_limits(SolveLimits::default().with_timeout(timeout))
+            .is_solved()
+    }
+
+    /// Searches until the puzzle is solved or one of the limits is reached.
+    ///
+    /// Expanded nodes are counted from the start of this call. The transposition
+    /// table has a fixed size, so the tree size limit is never reached.
+    pub fn solve_with_limits(&mut self, limits: SolveLimits) -> SolveOutcome {
+        self.timeout_at = limits.timeout.map(|timeout| Instant::now() + timeout);
+        self.expanded_count_limit = limits
+            .max_expanded_nodes
+            .map(|max_expanded_nodes| self.expanded_count + max_expanded_nodes as u64);
+
+        self.solve_root();
+
+        if self.is_proved() {
+            SolveOutcome::Proved
+        } else if self.is_solved() {
+            SolveOutcome::Disproved
+        } else if self.has_reached_expanded_count_limit() {
+            SolveOutcome::NodeLimit
+        } else {
+            SolveOutcome::TimedOut
+        }
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.is_proved() || self.root_delta == ProofNumber::finite(0)
+    }
+
+    pub fn is_proved(&self) -> bool {
+        self.root_phi == ProofNumber::finite(0)
+    }
+
+    /// A move that wins for the player to move, if one has been found.
+    pub fn first_move(&self) -> Option<Move> {
+        self.winning_moves().next()
+    }
+
+    /// The moves found to win for the player to move.
+    pub(super) fn winning_moves(&self) -> impl Iterator<Item = Move> + '_ {
+        self.root_children
+            .iter()
+            .filter(|(_, delta)| *delta == ProofNumber::finite(0))
+            .map(|(go_move, _)| *go_move)
+    }
+
+    fn has_reached_expanded_count_limit(&self) -> bool {
+        self.expanded_count_limit
+            .is_some_and(|expanded_count_limit| self.expanded_count >= expanded_count_limit)
+            || matches!(&self.table, Table::Shared(table) if table.has_reached_expanded_count_limit())
+    }
+
+    fn solve_root(&mut self) {
+        self.is_stopped = false;
+
+        let (phi, delta, _) = self.multiple_iterative_deepening(
+            self.game,
+            ProofNumber::infinite(),
+            ProofNumber::infinite(),
+        );
+
+        self.root_phi = phi;
+        self.root_delta = delta;
+    }
+
+    /// Values of a position that hasn't been searched yet.
+    fn initial_values(&self, game: GoGame) -> (ProofNumber, ProofNumber) {
+        if let Some(status) =
+            terminal_detection::is_terminal(game, self.goal.attacker, self.goal.targets)
+        {
+            let winner = status.winner(self.goal.attacker, self.goal.seki_is_life);
+
+            if winner == game.current_player {
+                (ProofNumber::finite(0), ProofNumber::infinite())
+            } else {
+                (ProofNumber::infinite(), ProofNumber::finite(0))
+            }
+        } else {
+            self.table
+                .lookup(&game)
+                .unwrap_or((ProofNumber::finite(1), ProofNumber::finite(1)))
+        }
+    }
+
+    /// Searches `game` until either threshold is reached, returning its values
+    /// and whether they depend on the path taken to reach it.
+    ///
+    /// Values that depend on the path are not stored in the transposition
+    /// table, as they could be wrong when the position is reached another way.
+    fn multiple_iterative_deepening(
+        &mut self,
+        game: GoGame,
+        phi_threshold: ProofNumber,
+        delta_threshold: ProofNumber,
+    ) -> (ProofNumber, ProofNumber, bool) {
+        let expanded_count_before = self.expanded_count;
+        self.expanded_count += 1;
+
+        self.path.push(game);
+
+        if let Table::Shared(table) = &self.table {
+            table.enter(&game);
+        }
+
+        let mut moves = game.generate_moves();
+
+        if self.prunes_irrelevant_moves {
+            relevance_zone::prune_moves(game, self.goal.attacker, self.goal.targets, &mut moves);
+        }
+
+        let move_count = moves.len();
+
+        // A move back to a position on the current path is treated as illegal,
+        // as it would be under a positional superko rule.
+        let mut children: Vec<Child> = moves
+            .into_iter()
+            .filter(|(child, _)| !self.path.contains(child))
+            .map(|(child, go_move)| {
+                let (phi, delta) = self.initial_values(child);
+
+                Child {
+                    game: child,
+                    go_move,
+                    phi,
+                    delta,
+                    is_path_dependent: false,
+                }
+            })
+            .collect();
+
+        let has_repetition = children.len() != move_count;
+
+        self.profiler.expand_node(game, children.len() as u8);
+
+        let (phi, delta) = loop {
+            // Other threads may have made progress on the children since they
+            // were last looked at, or solved the puzzle.
+            if let Table::Shared(table) = &self.table {
+                if table.is_finished() {
+                    self.is_stopped = true;
+                }
+
+                for child in children.iter_mut() {
+                    if let Some((phi, delta)) = table.lookup(&child.game) {
+                        if !child.is_path_dependent {
+                            child.phi = phi;
+                            child.delta = delta;
+                        }
+                    }
+                }
+            }
+
+            let phi = children
+                .iter()
+                .map(|child| child.delta)
+                .min()
+                .unwrap_or_else(ProofNumber::infinite);
+            let delta = children.iter().map(|child| child.phi).sum::<ProofNumber>();
+
+            if phi >= phi_threshold || delta >= delta_threshold || self.is_stopped {
+                break (phi, delta);
+            }
+
+            let mut best_index = 0;
+            let mut best_delta = self.table.selection_delta(&children[0]);
+            let mut second_best_delta = ProofNumber::infinite();
+            for (index, child) in children.iter().enumerate().skip(1) {
+                let delta = self.table.selection_delta(child);
+
+                if delta < best_delta {
+                    second_best_delta = best_delta;
+                    best_index = index;
+                    best_delta = delta;
+                } else if delta < second_best_delta {
+                    second_best_delta = delta;
+                }
+            }
+
+            let best = &children[best_index];
+            let child_phi_threshold = delta_threshold - delta + best.phi;
+            let child_delta_threshold =
+                phi_threshold.min(second_best_delta + ProofNumber::finite(1));
+
+            self.profiler.move_down();
+            let (child_phi, child_delta, is_path_dependent) = self.multiple_iterative_deepening(
+                best.game,
+                child_phi_threshold,
+                child_delta_threshold,
+            );
+            self.profiler.move_up();
+
+            let best = &mut children[best_index];
+            best.phi = child_phi;
+            best.delta = child_delta;
+            best.is_path_dependent = is_path_dependent;
+
+            if let Some(timeout_at) = self.timeout_at {
+                if Instant::now() > timeout_at {
+                    self.is_stopped = true;
+                }
+            }
+
+            if self.has_reached_expanded_count_limit() {
+                self.is_stopped = true;
+            }
+        };
+
+        self.path.pop();
+
+        if let Table::Shared(table) = &self.table {
+            table.leave(&game);
+        }
+
+        let is_path_dependent =
+            has_repetition || children.iter().any(|child| child.is_path_dependent);
+
+        if !is_path_dependent {
+            self.table.store(Entry {
+                game,
+                phi,
+                delta,
+                work: self.expanded_count.saturating_sub(expanded_count_before),
+            });
+        }
+
+        if self.path.is_empty() {
+            self.root_children = children
+                .iter()
+                .map(|child| (child.go_move, child.delta))
+                .collect();
+        }
+
+        (phi, delta, is_path_dependent)
+    }
+}
+
+impl<P: Profiler> Solver for Dfpn<P> {
+    fn with_goal(game: GoGame, goal: Goal) -> Dfpn<P> {
+        Self::with_table(
+            game,
+            goal,
+            Table::Local(TranspositionTable::new(DEFAULT_TABLE_SIZE)),
+        )
+    }
+
+    fn search(&mut self, limits: SolveLimits) -> SolveResult {
+        let start = Instant::now();
+        let start_expanded_count = self.expanded_count;
+
+        let outcome = self.solve_with_limits(limits);
+
+        SolveResult {
+            outcome,
+            winning_moves: self.winning_moves().collect(),
+            stats: SolveStats {
+                expanded_nodes: (self.expanded_count - start_expanded_count) as usize,
+                elapsed: start.elapsed(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::go::BoardPosition;
+    use crate::puzzle::{NoProfile, Profile};
+    use insta::assert_display_snapshot;
+
+    #[test]
+    fn true_simple1() {
+        let mut solver =
+            Dfpn::<Profile>::from_sgf(include_str!("../test_sgfs/puzzles/true_simple1.sgf"));
+
+        solver.solve();
+
+        assert!(solver.is_proved());
+        assert_eq!(
+            solver.first_move(),
+            Some(Move::Place(BoardPosition::new(4, 0)))
+        );
+        assert_display_snapshot!(solver.profiler.node_count, @"80");
+    }
+
+    #[test]
+    fn true_simple4() {
+        let mut solver =
+            Dfpn::<NoProfile>::from_sgf(include_str!("../test_sgfs/puzzles/true_simple4.sgf"));
+
+        solver.solve();
+
+        assert!(solver.is_proved());
+        assert_eq!(
+            solver.first_move(),
+            Some(Move::Place(BoardPosition::new(7, 0)))
+        );
+    }
+
+    #[test]
+    fn solves_with_tiny_table() {
+        let mut solver = Dfpn::<NoProfile>::with_table_size(
+            GoGame::from_sgf(include_str!("../test_sgfs/puzzles/true_simple2.sgf")),
+            16,
+        );
+
+        solver.solve();
+
+        assert!(solver.is_proved());
+        assert_eq!(
+            solver.first_move(),
+            Some(Move::Place(BoardPosition::new(2, 1)))
+        );
+    }
+}
@@ -0,0 +1,483 @@
+//! Thomsen's lambda search, a threat based alternative to proof-number search.
+//!
+//! A λⁿ-move is an attacker move after which, if the defender passed, the
+//! attacker would win with a λⁱ-tree for some i < n. A λ⁰-move wins straight
+//! away. A λⁿ-tree only tries λⁿ-moves for the attacker. The defender tries
+//! every move, but a move that leaves the attacker a lower order win loses
+//! at once. The search tries λ⁰, λ¹, λ² and so on until the attacker wins.
+//!
+//! A λⁿ-tree without a win for the attacker doesn't prove that the attacker
+//! can't win, as the moves left out might. The defender can always pass
+//! though, so a move is refuted for certain when the attacker can't win even
+//! with the extra move. Once every move in a tree is refuted for certain the
+//! defender wins, and the search stops with that answer.
+//!
+//! Capturing problems come down to a few threats in a row and are solved
+//! fast. Showing that the defender lives takes a search of the whole tree.
+
+use super::solve_limits::{SolveLimits, SolveOutcome};
+use super::solver::{Goal, SolveResult, SolveStats, Solver};
+use super::{terminal_detection, Profiler};
+use crate::go::{GoGame, GoPlayer, Move};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// The result of searching a λ-tree.
+#[derive(Clone, Copy, Debug)]
+struct Value {
+    attacker_wins: bool,
+    /// Whether the result holds for trees of every order.
+    ///
+    /// A win always does, so this only tells apart a loss for
+    /// the attacker from a tree that was too small to find a win.
+    is_exact: bool,
+    /// Whether a move was left out for repeating a position on the path.
+    is_path_dependent: bool,
+}
+
+impl Value {
+    fn exact(attacker_wins: bool) -> Value {
+        Value {
+            attacker_wins,
+            is_exact: true,
+            is_path_dependent: false,
+        }
+    }
+
+    fn unknown() -> Value {
+        Value {
+            attacker_wins: false,
+            is_exact: false,
+            is_path_dependent: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Entry {
+    order: u32,
+    value: Value,
+}
+
+pub struct LambdaSearch<P: Profiler> {
+    /// What each player is trying to achieve.
+    goal: Goal,
+    game: GoGame,
+    /// The positions on the path from the root to the node being searched.
+    path: Vec<GoGame>,
+    /// Values of the positions searched so far, with the
+    /// order of the tree each one was searched with.
+    table: HashMap<GoGame, Entry>,
+    /// The order of the tree searched at the root.
+    order: u32,
+    /// Whether the player to move at the root wins, once known.
+    result: Option<bool>,
+    first_move: Option<Move>,
+    expanded_count: usize,
+    limits: SolveLimits,
+    timeout_at: Option<Instant>,
+    /// Why the search was stopped early, if it was.
+    stopped: Option<SolveOutcome>,
+    pub profiler: P,
+}
+
+impl<P: Profiler> LambdaSearch<P> {
+    pub fn new(game: GoGame) -> LambdaSearch<P> {
+        Self::with_goal(game, Goal::guess(game))
+    }
+
+    pub fn from_sgf(sgf_string: &str) -> LambdaSearch<P> {
+        Self::new(GoGame::from_sgf(sgf_string))
+    }
+
+    pub fn solve(&mut self) {
+        self.solve_with_limits(SolveLimits::default());
+    }
+
+    pub fn solve_with_timeout(&mut self, timeout: Duration) -> bool {
+        self.solve_with_limits(SolveLimits::default().with_timeout(timeout))
+            .is_solved()
+    }
+
+    /// Searches until the puzzle is solved or one of the limits is reached.
+    ///
+    /// The tree size limit bounds the number of positions in the table.
+    /// A search that's stopped early carries on from the order
+    /// it had reached, and with its table, when it's resumed.
+    pub fn solve_with_limits(&mut self, limits: SolveLimits) -> SolveOutcome {
+        self.limits = limits;
+        self.timeout_at = limits.timeout.map(|timeout| Instant::now() + timeout);
+        self.expanded_count = 0;
+        self.stopped = None;
+
+        let attacker_to_move = self.game.current_player == self.goal.attacker;
+
+        while self.result.is_none() {
+            let value = if attacker_to_move {
+                self.attacker_node(self.game, self.order)
+            } else {
+                self.defender_node(self.game, self.order)
+            };
+
+            if let Some(outcome) = self.stopped {
+                return outcome;
+            }
+
+            if value.attacker_wins || value.is_exact {
+                self.result = Some(value.attacker_wins == attacker_to_move);
+            } else {
+                self.order += 1;
+            }
+        }
+
+        if self.is_proved() {
+            SolveOutcome::Proved
+        } else {
+            SolveOutcome::Disproved
+        }
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.result.is_some()
+    }
+
+    pub fn is_proved(&self) -> bool {
+        self.result == Some(true)
+    }
+
+    /// A move that wins for the player to move, if one has been found.
+    pub fn first_move(&self) -> Option<Move> {
+        if self.is_proved() {
+            self.first_move
+        } else {
+            None
+        }
+    }
+
+    /// The order of the tree searched last, which is
+    /// the order the answer was found at once solved.
+    pub fn order(&self) -> u32 {
+        self.order
+    }
+
+    /// The winner of a terminal position.
+    fn terminal_winner(&self, game: GoGame) -> Option<GoPlayer> {
+        terminal_detection::is_terminal(game, self.goal.attacker, self.goal.targets)
+            .map(|status| status.winner(self.goal.attacker, self.goal.seki_is_life))
+    }
+
+    /// Counts a node, returning false if the search has to stop.
+    fn enter(&mut self, game: GoGame) -> bool {
+        if self.stopped.is_some() {
+            return false;
+        }
+
+        self.expanded_count += 1;
+
+        if self
+            .limits
+            .max_expanded_nodes
+            .is_some_and(|max_expanded_nodes| self.expanded_count > max_expanded_nodes)
+        {
+            self.stopped = Some(SolveOutcome::NodeLimit);
+        } else if self
+            .limits
+            .max_tree_size
+            .is_some_and(|max_tree_size| self.table.len() > max_tree_size)
+        {
+            self.stopped = Some(SolveOutcome::MemoryLimit);
+        } else if self
+            .timeout_at
+            .is_some_and(|timeout_at| Instant::now() > timeout_at)
+        {
+            self.stopped = Some(SolveOutcome::TimedOut);
+        }
+
+        if self.stopped.is_some() {
+            return false;
+        }
+
+        self.path.push(game);
+        self.profiler.move_down();
+
+        true
+    }
+
+    fn leave(&mut self) {
+        self.path.pop();
+        self.profiler.move_up();
+    }
+
+    /// The legal moves, and whether any were left out for moving back to
+    /// a position on the path, which is illegal under a positional superko rule.
+    fn legal_moves(&mut self, game: GoGame) -> (Vec<(GoGame, Move)>, bool) {
+        let moves = game.generate_moves();
+        let move_count = moves.len();
+
+        let moves: Vec<_> = moves
+            .into_iter()
+            .filter(|(child, _)| !self.path.contains(child))
+            .collect();
+
+        self.profiler.expand_node(game, moves.len() as u8);
+
+        let has_repetition = moves.len() != move_count;
+
+        (moves, has_repetition)
+    }
+
+    /// Looks up a value stored for `game` that holds at `order`.
+    ///
+    /// A win at some order is a win at every higher order,
+    /// and a loss at some order is a loss at every lower order.
+    fn lookup(&self, game: GoGame, order: u32) -> Option<Value> {
+        let entry = self.table.get(&game)?;
+
+        if entry.value.attacker_wins {
+            Some(entry.value).filter(|_| entry.order <= order)
+        } else {
+            Some(entry.value).filter(|value| value.is_exact || entry.order >= order)
+        }
+    }
+
+    /// Stores the value of a searched position, unless it depends on the path
+    /// taken to reach it, as it could be wrong when reached another way.
+    fn store(&mut self, game: GoGame, order: u32, value: Value) {
+        if self.stopped.is_none() && !value.is_path_dependent {
+            self.table.insert(game, Entry { order, value });
+        }
+    }
+
+    /// The value for the attacker, to move, of the
+    /// first λⁱ-tree with i ≤ `order` that wins or is exact.
+    fn attacker_up_to(&mut self, game: GoGame, order: u32) -> Value {
+        let mut value = self.attacker_node(game, 0);
+
+        for order in 1..=order {
+            if value.attacker_wins || value.is_exact {
+                break;
+            }
+
+            value = self.attacker_node(game, order);
+        }
+
+        value
+    }
+
+    /// Whether the attacker, having just played to reach `game`, threatens to
+    /// win with a λⁱ-tree for some i ≤ `order`, by searching what happens if
+    /// the defender passes.
+    ///
+    /// A move that isn't a threat is refuted by the pass,
+    /// so the value of the pass is the value of the move.
+    fn threat(&mut self, game: GoGame, order: u32) -> Value {
+        let passed = game.pass();
+
+        // Passing is illegal here, so the move is searched as if it were a threat.
+        if self.path.contains(&passed) {
+            return Value {
+                is_path_dependent: true,
+                ..Value::exact(true)
+            };
+        }
+
+        self.path.push(game);
+        let value = self.attacker_up_to(passed, order);
+        self.path.pop();
+
+        value
+    }
+
+    /// The value of a terminal position.
+    fn terminal_value(&self, game: GoGame) -> Option<Value> {
+        self.terminal_winner(game)
+            .map(|winner| Value::exact(winner == self.goal.attacker))
+    }
+
+    /// The value of a λ-tree of order `order` with the attacker to move.
+    fn attacker_node(&mut self, game: GoGame, order: u32) -> Value {
+        // The root is searched even if it's terminal, to find a first move.
+        let is_root = self.path.is_empty();
+
+        if !is_root {
+            if let Some(value) = self
+                .terminal_value(game)
+                .or_else(|| self.lookup(game, order))
+            {
+                return value;
+            }
+        }
+
+        if !self.enter(game) {
+            return Value::unknown();
+        }
+
+        let (moves, has_repetition) = self.legal_moves(game);
+        let mut value = Value {
+            is_path_dependent: has_repetition,
+            ..Value::exact(false)
+        };
+
+        for (child, go_move) in moves {
+            let child_value = match self.terminal_value(child) {
+                Some(child_value) => child_value,
+                None if order == 0 => Value::unknown(),
+                None => {
+                    let threat = self.threat(child, order - 1);
+
+                    if threat.attacker_wins {
+                        let child_value = self.defender_node(child, order);
+
+                        Value {
+                            is_path_dependent: threat.is_path_dependent
+                                || child_value.is_path_dependent,
+                            ..child_value
+                        }
+                    } else {
+                        threat
+                    }
+                }
+            };
+
+            value.is_path_dependent |= child_value.is_path_dependent;
+
+            if child_value.attacker_wins {
+                if is_root {
+                    self.first_move = Some(go_move);
+                }
+
+                value.attacker_wins = true;
+                value.is_exact = true;
+                break;
+            }
+
+            value.is_exact &= child_value.is_exact;
+        }
+
+        self.leave();
+        self.store(game, order, value);
+
+        value
+    }
+
+    /// The value of a λ-tree of order `order` with the defender to move.
+    ///
+    /// A defender move that leaves the attacker a win of a lower
+    /// order loses straight away, so it isn't searched any further.
+    fn defender_node(&mut self, game: GoGame, order: u32) -> Value {
+        // The root is searched even if it's terminal, to find a first move.
+        let is_root = self.path.is_empty();
+
+        if !is_root {
+            if let Some(value) = self
+                .terminal_value(game)
+                .or_else(|| self.lookup(game, order))
+            {
+                return value;
+            }
+        }
+
+        if !self.enter(game) {
+            return Value::unknown();
+        }
+
+        let (moves, has_repetition) = self.legal_moves(game);
+        let mut value = Value {
+            is_path_dependent: has_repetition,
+            ..Value::exact(true)
+        };
+
+        for (child, go_move) in moves {
+            let child_value = self.attacker_up_to(child, order);
+
+            value.is_path_dependent |= child_value.is_path_dependent;
+
+            if !child_value.attacker_wins {
+                if is_root {
+                    self.first_move = Some(go_move);
+                }
+
+                value.attacker_wins = false;
+                value.is_exact = child_value.is_exact;
+                break;
+            }
+        }
+
+        self.leave();
+        self.store(game, order, value);
+
+        value
+    }
+}
+
+impl<P: Profiler> Solver for LambdaSearch<P> {
+    fn with_goal(game: GoGame, goal: Goal) -> LambdaSearch<P> {
+        LambdaSearch {
+            goal,
+            game,
+            path: Vec::new(),
+            table: HashMap::new(),
+            order: 0,
+            result: None,
+            first_move: None,
+            expanded_count: 0,
+            limits: SolveLimits::default(),
+            timeout_at: None,
+            stopped: None,
+            profiler: P::new(),
+        }
+    }
+
+    fn search(&mut self, limits: SolveLimits) -> SolveResult {
+        let start = Instant::now();
+
+        let outcome = self.solve_with_limits(limits);
+
+        SolveResult {
+            outcome,
+            winning_moves: self.first_move().into_iter().collect(),
+            stats: SolveStats {
+                expanded_nodes: self.expanded_count,
+                elapsed: start.elapsed(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::go::BoardPosition;
+    use crate::puzzle::NoProfile;
+    use insta::assert_display_snapshot;
+
+    #[test]
+    fn true_simple1() {
+        let mut solver = LambdaSearch::<NoProfile>::from_sgf(include_str!(
+            "../test_sgfs/puzzles/true_simple1.sgf"
+        ));
+
+        solver.solve();
+
+        assert!(solver.is_proved());
+        assert_eq!(
+            solver.first_move(),
+            Some(Move::Place(BoardPosition::new(4, 0)))
+        );
+        assert_display_snapshot!(solver.order(), @"6");
+    }
+
+    #[test]
+    fn stops_at_node_limit() {
+        let mut solver = LambdaSearch::<NoProfile>::from_sgf(include_str!(
+            "../test_sgfs/puzzles/true_simple1.sgf"
+        ));
+
+        let limits = SolveLimits::default().with_max_expanded_nodes(1);
+
+        assert_eq!(solver.solve_with_limits(limits), SolveOutcome::NodeLimit);
+        assert!(!solver.is_solved());
+
+        assert!(solver.solve_with_limits(SolveLimits::default()).is_solved());
+    }
+}
@@ -0,0 +1,117 @@
+//! Initial proof and disproof numbers for newly created leaves.
+//!
+//! By default every leaf starts with both numbers at 1, so the search is guided
+//! only by the shape of the tree. An evaluator can instead use knowledge of Go
+//! to guess how hard each side will find it to win from a position, which lets
+//! the search go for the most promising moves first.
+
+use super::terminal_detection::safe_attacker_stones;
+use crate::go::{GoGame, GoPlayer};
+
+/// Estimates how much work each side needs to win from a position.
+pub trait LeafEvaluator {
+    /// Returns the estimated cost of killing the defender and the estimated
+    /// cost of living, which are used as the proof number of the attacker
+    /// and the proof number of the defender. A cost of 0 is treated as 1.
+    fn evaluate(&self, game: &GoGame, attacker: GoPlayer) -> (u32, u32);
+}
+
+/// Gives every leaf the same cost for each side.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ConstantEvaluator;
+
+impl LeafEvaluator for ConstantEvaluator {
+    fn evaluate(&self, _game: &GoGame, _attacker: GoPlayer) -> (u32, u32) {
+        (1, 1)
+    }
+}
+
+/// The most moves that capturing a defender block is counted as needing,
+/// which is also what it counts as when none of them can be captured.
+const MAX_CAPTURE_MOVES: u32 = 6;
+
+/// Estimates the costs from the defender's eye space and liberties.
+///
+/// * Killing is harder the more eye space the defender has, as the attacker
+///   needs about one move for every two interior points to reduce it.
+/// * Killing takes at least as many attacker moves as the defender's weakest
+///   block that can be captured has liberties.
+/// * Living is harder for each attacker stone that would have to be captured
+///   inside the eye space, and harder again for those not touching any of the
+///   defender's stones, which have to be surrounded first.
+/// * Living is also harder when a defender block is already in atari.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct KnowledgeEvaluator;
+
+impl LeafEvaluator for KnowledgeEvaluator {
+    fn evaluate(&self, game: &GoGame, attacker: GoPlayer) -> (u32, u32) {
+        let board = game.board;
+        let defender = attacker.flip();
+        let defender_stones = board.get_bitboard_for_player(defender) & !board.out_of_bounds();
+        let safe_attacker_stones = safe_attacker_stones(board, attacker);
+
+        let eye_space = !safe_attacker_stones & !board.out_of_bounds();
+        let reduction_moves = 1 + eye_space.interior().count() / 2;
+
+        let liberty_counts: Vec<_> = (defender_stones & !board.safe_blocks_for_player(defender))
+            .groups()
+            .map(|block| (block.immediate_exterior() & board.in_bounds_empty_cells()).count())
+            .collect();
+        let capture_moves = liberty_counts
+            .iter()
+            .copied()
+            .min()
+            .unwrap_or(MAX_CAPTURE_MOVES)
+            .min(MAX_CAPTURE_MOVES);
+
+        let kill_cost = reduction_moves.max(capture_moves);
+
+        let inner_attacker_stones = board.get_bitboard_for_player(attacker) & !safe_attacker_stones;
+        let distant_attacker_stones = inner_attacker_stones & !defender_stones.expand_one();
+        let is_in_atari = liberty_counts.contains(&1);
+        let live_cost = 1
+            + inner_attacker_stones.count()
+            + distant_attacker_stones.count()
+            + is_in_atari as u32;
+
+        (kill_cost, live_cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::{Profile, Puzzle};
+    use insta::assert_debug_snapshot;
+
+    #[test]
+    fn knowledge_evaluator() {
+        let game = GoGame::from_sgf(include_str!("../test_sgfs/puzzles/true_simple1.sgf"));
+
+        assert_debug_snapshot!(KnowledgeEvaluator.evaluate(&game, GoPlayer::White), @r###"
+        (
+            4,
+            2,
+        )
+        "###);
+    }
+
+    fn expanded_nodes<E: LeafEvaluator + Send + Sync + 'static>(game: GoGame, evaluator: E) -> u32 {
+        let mut puzzle = Puzzle::<Profile>::new(game);
+        puzzle.set_leaf_evaluator(evaluator);
+        puzzle.solve();
+
+        assert!(puzzle.is_proved());
+        puzzle.profiler.node_count
+    }
+
+    #[test]
+    fn expands_fewer_nodes_than_constant_evaluator() {
+        let game = GoGame::from_sgf(include_str!("../test_sgfs/puzzles/true_simple4.sgf"));
+
+        let constant = expanded_nodes(game, ConstantEvaluator);
+        let knowledge = expanded_nodes(game, KnowledgeEvaluator);
+
+        assert!(knowledge < constant, "{} >= {}", knowledge, constant);
+    }
+}
@@ -0,0 +1,194 @@
+use super::{guess_attacker, Goal, NoProfile, Puzzle, Solver};
+use crate::go::{GoGame, GoPlayer};
+
+/// The life and death status of the defender, with the player to move
+/// moving first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The defender lives unconditionally, even if the attacker
+    /// wins every ko.
+    Alive,
+    /// The defender can't be killed, even if the attacker wins every ko,
+    /// but can only live in seki.
+    Seki,
+    /// The result depends on a ko. The given player wins when
+    /// neither player has any ko threats.
+    Ko(GoPlayer),
+    /// The defender dies, even if the defender wins every ko.
+    Dead,
+}
+
+impl Outcome {
+    /// Solves the game, guessing the attacker from which player
+    /// has stones touching the out of bounds area.
+    pub fn solve(game: GoGame) -> Outcome {
+        Self::solve_with_attacker(game, guess_attacker(game))
+    }
+
+    /// Solves the game several times, with each player in turn winning every ko
+    /// and with a seki counted either as life or as death.
+    ///
+    /// Any ko master or ko threats the game already has are ignored.
+    pub(crate) fn solve_with_attacker(game: GoGame, attacker: GoPlayer) -> Outcome {
+        let defender = attacker.flip();
+
+        let game = game.with_ko_master(None).with_ko_threats(0, 0);
+        let attacker_wins_kos = game.with_ko_master(Some(attacker));
+        let defender_wins_kos = game.with_ko_master(Some(defender));
+
+        if defender_wins(attacker_wins_kos, attacker, false) {
+            Outcome::Alive
+        } else if defender_wins(attacker_wins_kos, attacker, true) {
+            Outcome::Seki
+        } else if !defender_wins(defender_wins_kos, attacker, true) {
+            Outcome::Dead
+        } else if defender_wins(game, attacker, true) {
+            Outcome::Ko(defender)
+        } else {
+            Outcome::Ko(attacker)
+        }
+    }
+}
+
+/// The winner of a game given the ko threats each player has,
+/// along with how many of those threats the winner needs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KoResult {
+    pub winner: GoPlayer,
+    /// The fewest ko threats the winner needs to still win,
+    /// with the loser keeping all of theirs.
+    pub threats_needed: u8,
+}
+
+impl KoResult {
+    /// Solves the game, guessing the attacker from which player
+    /// has stones touching the out of bounds area.
+    pub fn solve(game: GoGame) -> KoResult {
+        Self::solve_with_attacker(game, guess_attacker(game))
+    }
+
+    /// Solves the game once with the ko threats it was given, then again with
+    /// the winner given more and more threats until they win with that many.
+    pub(crate) fn solve_with_attacker(game: GoGame, attacker: GoPlayer) -> KoResult {
+        let winner = solve_winner(game, attacker);
+        let available = game.ko_threats(winner);
+
+        let threats_needed = (0..available)
+            .find(|&threats| {
+                solve_winner(with_ko_threats_for(game, winner, threats), attacker) == winner
+            })
+            .unwrap_or(available);
+
+        KoResult {
+            winner,
+            threats_needed,
+        }
+    }
+}
+
+fn with_ko_threats_for(game: GoGame, player: GoPlayer, threats: u8) -> GoGame {
+    match player {
+        GoPlayer::Black => game.with_ko_threats(threats, game.ko_threats(GoPlayer::White)),
+        GoPlayer::White => game.with_ko_threats(game.ko_threats(GoPlayer::Black), threats),
+    }
+}
+
+fn solve_winner(game: GoGame, attacker: GoPlayer) -> GoPlayer {
+    let mut puzzle = Puzzle::<NoProfile>::with_goal(game, Goal::new(attacker));
+    puzzle.solve();
+
+    if puzzle.is_proved() {
+        game.current_player
+    } else {
+        game.current_player.flip()
+    }
+}
+
+fn defender_wins(game: GoGame, attacker: GoPlayer, seki_is_life: bool) -> bool {
+    let goal = Goal::new(attacker).with_seki_is_life(seki_is_life);
+    let mut puzzle = Puzzle::<NoProfile>::with_goal(game, goal);
+    puzzle.solve();
+
+    puzzle.is_proved() == (game.current_player != attacker)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn true_simple1() {
+        let game = GoGame::from_sgf(include_str!("../test_sgfs/puzzles/true_simple1.sgf"));
+
+        assert_eq!(Outcome::solve(game), Outcome::Alive);
+        assert_eq!(
+            Outcome::solve(GoGame::from_board(game.board, GoPlayer::White)),
+            Outcome::Dead
+        );
+    }
+
+    #[test]
+    fn seki() {
+        let game = GoGame::from_sgf(include_str!("../test_sgfs/life_and_death/seki.sgf"));
+
+        assert_eq!(Outcome::solve(game), Outcome::Seki);
+        assert_eq!(
+            Outcome::solve(GoGame::from_board(game.board, GoPlayer::White)),
+            Outcome::Seki
+        );
+    }
+
+    #[test]
+    fn ko() {
+        let game = GoGame::from_sgf(include_str!("../test_sgfs/life_and_death/ko.sgf"));
+
+        assert_eq!(Outcome::solve(game), Outcome::Dead);
+        assert_eq!(
+            Outcome::solve(GoGame::from_board(game.board, GoPlayer::White)),
+            Outcome::Ko(GoPlayer::Black)
+        );
+    }
+
+    #[test]
+    fn ko_ignores_ko_threats() {
+        let game = GoGame::from_sgf(include_str!("../test_sgfs/life_and_death/ko.sgf"));
+        let game = GoGame::from_board(game.board, GoPlayer::White);
+
+        assert_eq!(
+            Outcome::solve(game.with_ko_threats(0, 3)),
+            Outcome::Ko(GoPlayer::Black)
+        );
+        assert_eq!(
+            Outcome::solve(game.with_ko_master(Some(GoPlayer::White))),
+            Outcome::Ko(GoPlayer::Black)
+        );
+    }
+
+    #[test]
+    fn ko_threats_needed() {
+        let game = GoGame::from_sgf(include_str!("../test_sgfs/life_and_death/ko.sgf"));
+        let game = GoGame::from_board(game.board, GoPlayer::White);
+
+        assert_eq!(
+            KoResult::solve(game),
+            KoResult {
+                winner: GoPlayer::Black,
+                threats_needed: 0
+            }
+        );
+        assert_eq!(
+            KoResult::solve(game.with_ko_threats(0, 3)),
+            KoResult {
+                winner: GoPlayer::White,
+                threats_needed: 1
+            }
+        );
+        assert_eq!(
+            KoResult::solve(game.with_ko_threats(2, 5)),
+            KoResult {
+                winner: GoPlayer::White,
+                threats_needed: 3
+            }
+        );
+    }
+}
@@ -0,0 +1,358 @@
+//! A df-pn solver that searches a single puzzle on several threads.
+//!
+//! Each thread runs its own [`Dfpn`](../struct.Dfpn.html) search from the root,
+//! but they all share one transposition table, so each thread builds on the
+//! results of the others. Positions being searched by one thread look less
+//! promising to the rest, which spreads the threads over different moves.
+
+use super::dfpn::{Dfpn, SharedTable, DEFAULT_TABLE_SIZE};
+use super::proof_number::ProofNumber;
+use super::solve_limits::{SolveLimits, SolveOutcome};
+use super::solver::{Goal, SolveResult, SolveStats, Solver};
+use super::{CancellationToken, Profiler, Progress};
+use crate::go::{GoGame, Move};
+use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// The number of shards the table is split into for each thread.
+const SHARDS_PER_THREAD: usize = 16;
+
+/// How often the threads are checked on while reporting progress.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+pub struct ParallelDfpn<P: Profiler> {
+    game: GoGame,
+    /// What each player is trying to achieve.
+    goal: Goal,
+    thread_count: usize,
+    table_size: usize,
+    /// Whether moves outside the relevance zone are left out of the search.
+    prunes_irrelevant_moves: bool,
+    is_solved: bool,
+    is_proved: bool,
+    /// The moves found to win for the player to move.
+    winning_moves: Vec<Move>,
+    /// The number of nodes expanded by every thread together in the last solve.
+    expanded_count: usize,
+    /// The profiler of each thread from the last solve.
+    pub profilers: Vec<P>,
+}
+
+impl<P: Profiler> ParallelDfpn<P> {
+    /// Creates a solver using one thread for each available core.
+    pub fn new(game: GoGame) -> ParallelDfpn<P> {
+        Self::with_goal(game, Goal::guess(game))
+    }
+
+    pub fn from_sgf(sgf_string: &str) -> ParallelDfpn<P> {
+        Self::new(GoGame::from_sgf(sgf_string))
+    }
+
+    pub fn set_thread_count(&mut self, thread_count: usize) {
+        self.thread_count = thread_count.max(1);
+    }
+
+    /// Limits the shared transposition table to at most `table_size` entries.
+    pub fn set_table_size(&mut self, table_size: usize) {
+        self.table_size = table_size;
+    }
+
+    /// Sets whether to leave out moves that can't change the result.
+    /// See [`Puzzle::set_relevance_zone_pruning`](../struct.Puzzle.html#method.set_relevance_zone_pruning).
+    pub fn set_relevance_zone_pruning(&mut self, prunes_irrelevant_moves: bool) {
+        self.prunes_irrelevant_moves = prunes_irrelevant_moves;
+    }
+
+    pub fn solve(&mut self) {
+        self.solve_with_limits(SolveLimits::default());
+    }
+
+    pub fn solve_with_timeout(&mut self, timeout: Duration) -> bool {
+        self.solve_with_limits(SolveLimits::default().with_timeout(timeout))
+            .is_solved()
+    }
+
+    /// Searches until the puzzle is solved or one of the limits is reached.
+    ///
+    /// Expanded nodes are counted across every thread. The shared table is
+    /// made no larger than the tree size limit, so that limit is never reached.
+    pub fn solve_with_limits(&mut self, limits: SolveLimits) -> SolveOutcome {
+        let table = self.create_table(limits);
+        let handles = self.spawn_threads(&table, limits.timeout);
+
+        self.join_threads(handles, &table);
+
+        if self.is_proved() {
+            SolveOutcome::Proved
+        } else if self.is_solved() {
+            SolveOutcome::Disproved
+        } else if table.has_reached_expanded_count_limit() {
+            SolveOutcome::NodeLimit
+        } else {
+            SolveOutcome::TimedOut
+        }
+    }
+
+    /// Searches until the puzzle is solved or `cancellation` is cancelled,
+    /// returning whether it was solved.
+    ///
+    /// `on_progress` is called when the search starts, then at most once every
+    /// `progress_interval` while it runs, and once more when it stops. The
+    /// node count is the number of nodes expanded by every thread together.
+    pub fn solve_with_progress<F: FnMut(Progress)>(
+        &mut self,
+        cancellation: &CancellationToken,
+        progress_interval: Duration,
+        mut on_progress: F,
+    ) -> bool {
+        let start = Instant::now();
+        let mut next_progress_at = start;
+
+        let table = self.create_table(SolveLimits::default());
+        self.is_solved = false;
+        self.is_proved = false;
+
+        if cancellation.is_cancelled() {
+            table.finish();
+        }
+
+        let handles = self.spawn_threads(&table, None);
+
+        while handles.iter().any(|handle| !handle.is_finished()) {
+            if cancellation.is_cancelled() {
+                table.finish();
+            }
+
+            let now = Instant::now();
+
+            if now >= next_progress_at {
+                on_progress(self.progress(&table, now - start));
+                next_progress_at = now + progress_interval;
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+
+        self.join_threads(handles, &table);
+
+        on_progress(self.progress(&table, start.elapsed()));
+
+        self.is_solved()
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.is_solved
+    }
+
+    pub fn is_proved(&self) -> bool {
+        self.is_proved
+    }
+
+    /// A move that wins for the player to move, if one has been found.
+    pub fn first_move(&self) -> Option<Move> {
+        self.winning_moves.first().copied()
+    }
+
+    fn create_table(&self, limits: SolveLimits) -> Arc<SharedTable> {
+        let table_size = limits
+            .max_tree_size
+            .map_or(self.table_size, |max_tree_size| {
+                self.table_size.min(max_tree_size)
+            });
+
+        Arc::new(SharedTable::new(
+            table_size,
+            self.thread_count * SHARDS_PER_THREAD,
+            limits.max_expanded_nodes,
+        ))
+    }
+
+    /// Starts a solver on each thread, each of which runs until one of
+    /// them solves the puzzle or a limit is reached.
+    fn spawn_threads(
+        &self,
+        table: &Arc<SharedTable>,
+        timeout: Option<Duration>,
+    ) -> Vec<JoinHandle<Dfpn<P>>> {
+        (0..self.thread_count)
+            .map(|_| {
+                let table = Arc::clone(table);
+                let game = self.game;
+                let goal = self.goal;
+                let prunes_irrelevant_moves = self.prunes_irrelevant_moves;
+
+                thread::spawn(move || {
+                    let mut solver = Dfpn::<P>::with_shared_table(game, goal, Arc::clone(&table));
+                    solver.set_relevance_zone_pruning(prunes_irrelevant_moves);
+
+                    match timeout {
+                        Some(timeout) => {
+                            solver.solve_with_timeout(timeout);
+                        }
+                        None => solver.solve(),
+                    }
+
+                    if solver.is_solved() {
+                        table.finish();
+                    }
+
+                    solver
+                })
+            })
+            .collect()
+    }
+
+    /// Waits for every thread, taking the result from one that solved the puzzle.
+    fn join_threads(&mut self, handles: Vec<JoinHandle<Dfpn<P>>>, table: &SharedTable) {
+        let solvers: Vec<_> = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect();
+
+        let solved = solvers.iter().find(|solver| solver.is_solved());
+
+        self.is_solved = solved.is_some();
+        self.is_proved = solved.is_some_and(|solver| solver.is_proved());
+        self.winning_moves =
+            solved.map_or_else(Vec::new, |solver| solver.winning_moves().collect());
+        self.expanded_count = table.expanded_count();
+        self.profilers = solvers.into_iter().map(|solver| solver.profiler).collect();
+    }
+
+    fn progress(&self, table: &SharedTable, elapsed: Duration) -> Progress {
+        // The root isn't stored when its result depends on repetitions, so
+        // take a solved result from the thread that found it.
+        let (proof_number, disproof_number) = if self.is_proved() {
+            (ProofNumber::finite(0), ProofNumber::infinite())
+        } else if self.is_solved() {
+            (ProofNumber::infinite(), ProofNumber::finite(0))
+        } else {
+            table
+                .lookup(&self.game)
+                .unwrap_or((ProofNumber::finite(1), ProofNumber::finite(1)))
+        };
+
+        Progress {
+            proof_number,
+            disproof_number,
+            node_count: table.expanded_count(),
+            elapsed,
+        }
+    }
+}
+
+impl<P: Profiler> Solver for ParallelDfpn<P> {
+    fn with_goal(game: GoGame, goal: Goal) -> ParallelDfpn<P> {
+        ParallelDfpn {
+            game,
+            goal,
+            thread_count: thread::available_parallelism().map_or(1, |count| count.get()),
+            table_size: DEFAULT_TABLE_SIZE,
+            prunes_irrelevant_moves: false,
+            is_solved: false,
+            is_proved: false,
+            winning_moves: Vec::new(),
+            expanded_count: 0,
+            profilers: Vec::new(),
+        }
+    }
+
+    /// Searches until the puzzle is solved or one of the limits is reached.
+    ///
+    /// The threads don't keep their results between searches, so searching
+    /// again after a limit is reached starts from the beginning.
+    fn search(&mut self, limits: SolveLimits) -> SolveResult {
+        let start = Instant::now();
+
+        let outcome = self.solve_with_limits(limits);
+
+        SolveResult {
+            outcome,
+            winning_moves: self.winning_moves.clone(),
+            stats: SolveStats {
+                expanded_nodes: self.expanded_count,
+                elapsed: start.elapsed(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::go::BoardPosition;
+    use crate::puzzle::NoProfile;
+
+    #[test]
+    fn true_simple1() {
+        let mut solver = ParallelDfpn::<NoProfile>::from_sgf(include_str!(
+            "../test_sgfs/puzzles/true_simple1.sgf"
+        ));
+        solver.set_thread_count(4);
+
+        solver.solve();
+
+        assert!(solver.is_proved());
+        assert_eq!(
+            solver.first_move(),
+            Some(Move::Place(BoardPosition::new(4, 0)))
+        );
+    }
+
+    #[test]
+    fn stops_at_node_limit() {
+        let mut solver = ParallelDfpn::<NoProfile>::from_sgf(include_str!(
+            "../test_sgfs/puzzles/true_simple1.sgf"
+        ));
+        solver.set_thread_count(4);
+
+        let outcome = solver.solve_with_limits(SolveLimits::default().with_max_expanded_nodes(5));
+
+        assert_eq!(outcome, SolveOutcome::NodeLimit);
+        assert!(!solver.is_solved());
+
+        let outcome = solver.solve_with_limits(SolveLimits::default());
+
+        assert_eq!(outcome, SolveOutcome::Proved);
+    }
+
+    #[test]
+    fn solve_with_progress() {
+        let mut solver = ParallelDfpn::<NoProfile>::from_sgf(include_str!(
+            "../test_sgfs/puzzles/true_simple1.sgf"
+        ));
+        solver.set_thread_count(4);
+
+        let mut reports = Vec::new();
+        let solved = solver.solve_with_progress(
+            &CancellationToken::new(),
+            Duration::from_secs(0),
+            |progress| reports.push(progress),
+        );
+
+        assert!(solved);
+        assert!(solver.is_proved());
+
+        let last = reports.last().unwrap();
+        assert_eq!(last.proof_number, ProofNumber::finite(0));
+        assert!(last.node_count > 0);
+    }
+
+    #[test]
+    fn cancel_solve() {
+        let mut solver = ParallelDfpn::<NoProfile>::from_sgf(include_str!(
+            "../test_sgfs/puzzles/true_simple1.sgf"
+        ));
+        solver.set_thread_count(4);
+
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+
+        let solved = solver.solve_with_progress(&cancellation, Duration::from_secs(0), |_| {});
+
+        assert!(!solved);
+        assert!(!solver.is_solved());
+    }
+}
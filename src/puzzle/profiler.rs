@@ -1,7 +1,7 @@
 use crate::go::GoGame;
 use std::collections::HashSet;
 
-pub trait Profiler {
+pub trait Profiler: Send + 'static {
     fn new() -> Self;
     fn move_up(&mut self);
     fn move_down(&mut self);
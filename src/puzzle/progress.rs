@@ -0,0 +1,34 @@
+use super::proof_number::ProofNumber;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A handle for stopping a solve, which can be cloned and
+/// cancelled from another thread.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Stops every solve using this token or one of its clones,
+    /// at the end of its current iteration.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// How far a solve has got.
+#[derive(Clone, Copy, Debug)]
+pub struct Progress {
+    pub proof_number: ProofNumber,
+    pub disproof_number: ProofNumber,
+    pub node_count: usize,
+    pub elapsed: Duration,
+}
@@ -0,0 +1,526 @@
+//! Checks proof trees without using any of the search code, so that the result
+//! of a puzzle can be trusted without trusting the solver that found it.
+
+use super::proof_tree::{
+    ATTACKER_PROPERTY, SEKI_IS_LIFE_PROPERTY, TARGET_PROPERTY, WINNER_PROPERTY,
+};
+use super::terminal_detection;
+use super::{Goal, ProofTree};
+use crate::go::{BitBoard, BoardPosition, GoBoard, GoGame, GoPlayer, Move, MoveError};
+use sgf_parser::{GameNode, GameTree, SgfToken};
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// Why a proof tree doesn't prove its result.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProofErrorKind {
+    InvalidSgf(String),
+    /// A move can't be played in the position before it.
+    IllegalMove(MoveError),
+    /// A position isn't the one reached by playing its move.
+    WrongPosition,
+    /// A line ends in a position that the game isn't over in.
+    NotTerminal,
+    /// A line ends in a position won by the other player.
+    WrongWinner,
+    /// The winner is to move, but isn't given exactly one move.
+    ExpectedOneMove(usize),
+    /// The loser is to move, and this legal reply is missing.
+    MissingReply(Move),
+    /// The loser is to move, and this reply is given more than once.
+    RepeatedReply(Move),
+}
+
+/// A proof tree failing to prove its result, with the line of moves leading
+/// to the position the problem was found in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofError {
+    pub line: Vec<Move>,
+    pub kind: ProofErrorKind,
+}
+
+impl ProofError {
+    fn new(line: &[Move], kind: ProofErrorKind) -> ProofError {
+        ProofError {
+            line: line.to_vec(),
+            kind,
+        }
+    }
+}
+
+impl Display for ProofError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ProofErrorKind::InvalidSgf(message) => write!(f, "invalid SGF: {}", message)?,
+            ProofErrorKind::IllegalMove(error) => write!(f, "illegal move: {:?}", error)?,
+            ProofErrorKind::WrongPosition => write!(f, "position doesn't follow from its move")?,
+            ProofErrorKind::NotTerminal => write!(f, "line ends before the game is over")?,
+            ProofErrorKind::WrongWinner => write!(f, "line ends in a loss for the winner")?,
+            ProofErrorKind::ExpectedOneMove(count) => {
+                write!(f, "expected one move for the winner, found {}", count)?
+            }
+            ProofErrorKind::MissingReply(go_move) => write!(f, "missing reply {}", go_move)?,
+            ProofErrorKind::RepeatedReply(go_move) => write!(f, "repeated reply {}", go_move)?,
+        }
+
+        if !self.line.is_empty() {
+            let line: Vec<_> = self
+                .line
+                .iter()
+                .map(|go_move| go_move.to_string())
+                .collect();
+
+            write!(f, " after {}", line.join(" "))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for ProofError {}
+
+impl ProofTree {
+    /// Checks that the tree proves a win for `winner` under `goal`.
+    ///
+    /// Every position is replayed from the root with [`GoGame::play_move`],
+    /// and every line has to end in a position won by `winner`: either one
+    /// accepted by terminal detection, or one that repeats an earlier
+    /// position on the line, which loses for the player who repeated it.
+    pub fn check(&self, winner: GoPlayer, goal: Goal) -> Result<(), ProofError> {
+        self.check_after(&[], winner, goal)
+    }
+
+    /// Checks the tree like [`check`](ProofTree::check), with `previous_games`
+    /// played before the root, so that repeating one of them also ends a line.
+    /// The proof tree of a puzzle that was rerooted has to be checked against
+    /// its [`previous_games`](super::Puzzle::previous_games).
+    pub fn check_after(
+        &self,
+        previous_games: &[GoGame],
+        winner: GoPlayer,
+        goal: Goal,
+    ) -> Result<(), ProofError> {
+        self.check_from(winner, goal, &mut previous_games.to_vec(), &mut Vec::new())
+    }
+
+    fn check_from(
+        &self,
+        winner: GoPlayer,
+        goal: Goal,
+        path: &mut Vec<GoGame>,
+        line: &mut Vec<Move>,
+    ) -> Result<(), ProofError> {
+        let game = self.game;
+
+        if !line.is_empty() {
+            if path.contains(&game) {
+                if game.current_player != winner {
+                    return Err(ProofError::new(line, ProofErrorKind::WrongWinner));
+                }
+
+                return Ok(());
+            }
+
+            if let Some(status) = terminal_detection::is_terminal(game, goal.attacker, goal.targets)
+            {
+                if status.winner(goal.attacker, goal.seki_is_life) != winner {
+                    return Err(ProofError::new(line, ProofErrorKind::WrongWinner));
+                }
+
+                return Ok(());
+            }
+        }
+
+        if self.variations.is_empty() {
+            return Err(ProofError::new(line, ProofErrorKind::NotTerminal));
+        }
+
+        if game.current_player == winner {
+            if self.variations.len() != 1 {
+                return Err(ProofError::new(
+                    line,
+                    ProofErrorKind::ExpectedOneMove(self.variations.len()),
+                ));
+            }
+        } else {
+            let replies: Vec<Move> = self
+                .variations
+                .iter()
+                .map(|(go_move, _)| *go_move)
+                .collect();
+
+            for (i, go_move) in replies.iter().enumerate() {
+                if replies[..i].contains(go_move) {
+                    return Err(ProofError::new(
+                        line,
+                        ProofErrorKind::RepeatedReply(*go_move),
+                    ));
+                }
+            }
+
+            for (_, go_move) in game.generate_moves() {
+                if !replies.contains(&go_move) {
+                    return Err(ProofError::new(line, ProofErrorKind::MissingReply(go_move)));
+                }
+            }
+        }
+
+        path.push(game);
+
+        for (go_move, child) in self.variations.iter() {
+            line.push(*go_move);
+
+            match game.play_move(*go_move) {
+                Ok(next_game) if next_game == child.game => {}
+                Ok(_) => return Err(ProofError::new(line, ProofErrorKind::WrongPosition)),
+                Err(error) => {
+                    return Err(ProofError::new(line, ProofErrorKind::IllegalMove(error)))
+                }
+            }
+
+            child.check_from(winner, goal, path, line)?;
+
+            line.pop();
+        }
+
+        path.pop();
+
+        Ok(())
+    }
+
+    /// Reads a proof tree written by [`ProofTree::to_sgf`], along with the
+    /// winner and the goal it proves a win for.
+    ///
+    /// The first player to move is taken from the first move of the tree.
+    /// Ko masters, ko threats and positions before the root aren't recorded
+    /// in the SGF, so the root position is assumed to have none of them.
+    pub fn from_sgf(sgf_string: &str) -> Result<(ProofTree, GoPlayer, Goal), ProofError> {
+        let invalid =
+            |message: &str| ProofError::new(&[], ProofErrorKind::InvalidSgf(message.to_owned()));
+
+        let sgf = sgf_parser::parse(sgf_string).map_err(|error| invalid(&error.to_string()))?;
+
+        let (first_node, rest) = sgf
+            .nodes
+            .split_first()
+            .ok_or_else(|| invalid("the tree has no nodes"))?;
+
+        if first_node
+            .tokens
+            .iter()
+            .any(|token| matches!(token, SgfToken::Move { .. }))
+        {
+            return Err(invalid("the first node has a move"));
+        }
+
+        let board = GoBoard::from_sgf_node(first_node);
+
+        let mut attacker = None;
+        let mut winner = None;
+        let mut seki_is_life = false;
+        let mut targets = BitBoard::empty();
+
+        for token in first_node.tokens.iter() {
+            if let SgfToken::Unknown((identifier, value)) = token {
+                match identifier.as_str() {
+                    ATTACKER_PROPERTY => {
+                        attacker = Some(
+                            player_from_sgf(value).ok_or_else(|| invalid("unknown attacker"))?,
+                        )
+                    }
+                    WINNER_PROPERTY => {
+                        winner =
+                            Some(player_from_sgf(value).ok_or_else(|| invalid("unknown winner"))?)
+                    }
+                    SEKI_IS_LIFE_PROPERTY => seki_is_life = value == "1",
+                    TARGET_PROPERTY => {
+                        targets = targets
+                            .set(point_from_sgf(value).ok_or_else(|| invalid("invalid target"))?)
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let attacker = attacker.ok_or_else(|| invalid("the attacker isn't given"))?;
+        let winner = winner.ok_or_else(|| invalid("the winner isn't given"))?;
+        let goal = Goal::new(attacker)
+            .with_seki_is_life(seki_is_life)
+            .with_targets(board, targets);
+
+        let first_player = match rest
+            .first()
+            .or_else(|| sgf.variations.first()?.nodes.first())
+        {
+            Some(node) => {
+                Move::from_sgf_node(node)
+                    .ok_or_else(|| invalid("a node isn't a single move"))?
+                    .1
+            }
+            None => GoPlayer::Black,
+        };
+
+        let mut line = Vec::new();
+
+        let proof_tree = from_game_tree(
+            GoGame::from_board(board, first_player),
+            rest,
+            &sgf.variations,
+            &mut line,
+        )?;
+
+        Ok((proof_tree, winner, goal))
+    }
+}
+
+fn player_from_sgf(value: &str) -> Option<GoPlayer> {
+    match value {
+        "B" => Some(GoPlayer::Black),
+        "W" => Some(GoPlayer::White),
+        _ => None,
+    }
+}
+
+fn point_from_sgf(value: &str) -> Option<BoardPosition> {
+    match value.as_bytes() {
+        &[x, y] if x.is_ascii_lowercase() && y.is_ascii_lowercase() => {
+            let (x, y) = (x - b'a', y - b'a');
+
+            if x < BitBoard::width() && y < BitBoard::height() {
+                Some(BoardPosition::new(x, y))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+fn from_game_tree(
+    game: GoGame,
+    nodes: &[GameNode],
+    variations: &[GameTree],
+    line: &mut Vec<Move>,
+) -> Result<ProofTree, ProofError> {
+    let variations = match nodes.split_first() {
+        Some((node, rest)) => vec![from_node(game, node, rest, variations, line)?],
+        None => variations
+            .iter()
+            .map(|variation| match variation.nodes.split_first() {
+                Some((node, rest)) => from_node(game, node, rest, &variation.variations, line),
+                None => Err(ProofError::new(
+                    line,
+                    ProofErrorKind::InvalidSgf("a variation has no nodes".to_owned()),
+                )),
+            })
+            .collect::<Result<_, _>>()?,
+    };
+
+    Ok(ProofTree { game, variations })
+}
+
+fn from_node(
+    game: GoGame,
+    node: &GameNode,
+    rest: &[GameNode],
+    variations: &[GameTree],
+    line: &mut Vec<Move>,
+) -> Result<(Move, ProofTree), ProofError> {
+    let (go_move, player) = Move::from_sgf_node(node).ok_or_else(|| {
+        ProofError::new(
+            line,
+            ProofErrorKind::InvalidSgf("a node isn't a single move".to_owned()),
+        )
+    })?;
+
+    line.push(go_move);
+
+    let next_game = game
+        .play_move_for_player(go_move, player)
+        .map_err(|error| ProofError::new(line, ProofErrorKind::IllegalMove(error)))?;
+
+    let proof_tree = from_game_tree(next_game, rest, variations, line)?;
+
+    line.pop();
+
+    Ok((go_move, proof_tree))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::{NoProfile, Puzzle, Solver};
+
+    fn solve(game: GoGame) -> (ProofTree, GoPlayer, Goal) {
+        let mut puzzle = Puzzle::<NoProfile>::new(game);
+
+        puzzle.solve();
+
+        let winner = if puzzle.is_proved() {
+            game.current_player
+        } else {
+            game.current_player.flip()
+        };
+
+        (puzzle.proof_tree().unwrap(), winner, Goal::guess(game))
+    }
+
+    #[test]
+    fn accepts_proofs_from_both_sides() {
+        for sgf in [
+            include_str!("../test_sgfs/puzzles/true_simple1.sgf"),
+            include_str!("../test_sgfs/puzzles/ko_simple1.sgf"),
+        ]
+        .iter()
+        {
+            let game = GoGame::from_sgf(sgf);
+
+            for &player in GoPlayer::both() {
+                let (proof_tree, winner, goal) = solve(GoGame::from_board(game.board, player));
+
+                assert_eq!(proof_tree.check(winner, goal), Ok(()));
+
+                let (read_tree, read_winner, read_goal) =
+                    ProofTree::from_sgf(&proof_tree.to_sgf(winner, goal)).unwrap();
+
+                assert_eq!((read_winner, read_goal), (winner, goal));
+                assert_eq!(read_tree.check(winner, goal), Ok(()));
+            }
+        }
+    }
+
+    #[test]
+    fn reads_targets_and_seki_rule() {
+        let game = GoGame::from_board(
+            GoGame::from_sgf(include_str!("../test_sgfs/puzzles/target_capture1.sgf")).board,
+            GoPlayer::White,
+        );
+        let goal = Goal::guess(game)
+            .with_seki_is_life(true)
+            .with_targets(game.board, BitBoard::singleton(BoardPosition::new(6, 0)));
+
+        let mut puzzle = Puzzle::<NoProfile>::with_goal(game, goal);
+        puzzle.solve();
+        assert!(puzzle.is_proved());
+
+        let sgf = puzzle.proof_tree().unwrap().to_sgf(GoPlayer::White, goal);
+        let (proof_tree, winner, read_goal) = ProofTree::from_sgf(&sgf).unwrap();
+
+        assert_eq!((winner, read_goal), (GoPlayer::White, goal));
+        assert_eq!(proof_tree.check(winner, read_goal), Ok(()));
+    }
+
+    #[test]
+    fn rejects_sgf_without_a_goal() {
+        let game = GoGame::from_sgf(include_str!("../test_sgfs/puzzles/true_simple1.sgf"));
+
+        assert_eq!(
+            ProofTree::from_sgf(&game.board.to_sgf())
+                .map_err(|error| error.kind)
+                .err(),
+            Some(ProofErrorKind::InvalidSgf(
+                "the attacker isn't given".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn checks_rerooted_proofs_after_previous_games() {
+        let game = GoGame::from_sgf(include_str!("../test_sgfs/puzzles/ko_simple1.sgf"));
+        let game = GoGame::from_board(game.board, GoPlayer::White);
+
+        let mut puzzle = Puzzle::<NoProfile>::new(game);
+        puzzle.solve();
+        puzzle.reroot(puzzle.first_move().unwrap()).unwrap();
+        puzzle.solve();
+
+        let winner = if puzzle.is_proved() {
+            puzzle.current_game().current_player
+        } else {
+            puzzle.current_game().current_player.flip()
+        };
+        let proof_tree = puzzle.proof_tree().unwrap();
+
+        assert_eq!(
+            proof_tree.check_after(puzzle.previous_games(), winner, puzzle.goal()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn previous_games_count_as_repetitions() {
+        let game = GoGame::from_sgf(include_str!("../test_sgfs/puzzles/true_simple1.sgf"));
+        let (proof_tree, winner, goal) = solve(game);
+
+        let (first_move, first_tree) = &proof_tree.variations[0];
+
+        assert_eq!(
+            proof_tree.check_after(&[first_tree.game], winner, goal),
+            Err(ProofError {
+                line: vec![*first_move],
+                kind: ProofErrorKind::WrongWinner
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_the_wrong_winner() {
+        let game = GoGame::from_sgf(include_str!("../test_sgfs/puzzles/true_simple1.sgf"));
+        let (proof_tree, winner, goal) = solve(game);
+
+        assert!(proof_tree.check(winner.flip(), goal).is_err());
+    }
+
+    #[test]
+    fn rejects_a_missing_reply() {
+        let game = GoGame::from_sgf(include_str!("../test_sgfs/puzzles/true_simple1.sgf"));
+        let (mut proof_tree, winner, goal) = solve(game);
+
+        let (first_move, reply_tree) = &mut proof_tree.variations[0];
+        let first_move = *first_move;
+        let (reply, _) = reply_tree.variations.pop().unwrap();
+
+        assert_eq!(
+            proof_tree.check(winner, goal),
+            Err(ProofError {
+                line: vec![first_move],
+                kind: ProofErrorKind::MissingReply(reply)
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_an_unfinished_line() {
+        let game = GoGame::from_sgf(include_str!("../test_sgfs/puzzles/true_simple1.sgf"));
+        let (mut proof_tree, winner, goal) = solve(game);
+
+        proof_tree.variations[0].1.variations.clear();
+
+        assert_eq!(
+            proof_tree.check(winner, goal).map_err(|error| error.kind),
+            Err(ProofErrorKind::NotTerminal)
+        );
+    }
+
+    #[test]
+    fn rejects_an_illegal_move_in_sgf() {
+        let game = GoGame::from_sgf(include_str!("../test_sgfs/puzzles/true_simple1.sgf"));
+        let (mut proof_tree, _, _) = solve(game);
+
+        let occupied = game
+            .board
+            .get_bitboard_for_player(GoPlayer::Black)
+            .positions()
+            .next()
+            .unwrap();
+        proof_tree.variations[0].0 = Move::Place(occupied);
+
+        assert_eq!(
+            ProofTree::from_sgf(&proof_tree.to_sgf(GoPlayer::Black, Goal::guess(game)))
+                .map_err(|error| error.kind)
+                .err(),
+            Some(ProofErrorKind::IllegalMove(MoveError::Occupied))
+        );
+    }
+}
@@ -2,8 +2,10 @@ use std::cmp::Ordering;
 use std::fmt;
 use std::fmt::{Debug, Formatter};
 use std::iter::Sum;
-use std::ops::Add;
+use std::ops::{Add, Sub};
 
+/// A proof or disproof number. Finite numbers saturate at the largest
+/// finite number, rather than overflowing into infinity.
 #[derive(PartialEq, Eq, Copy, Clone)]
 pub struct ProofNumber(u32);
 
@@ -13,7 +15,16 @@ impl ProofNumber {
     }
 
     pub fn finite(n: u32) -> ProofNumber {
-        ProofNumber(n + 1)
+        ProofNumber(n.saturating_add(1))
+    }
+
+    /// The internal representation, with zero for infinity.
+    pub(crate) fn to_bits(self) -> u32 {
+        self.0
+    }
+
+    pub(crate) fn from_bits(bits: u32) -> ProofNumber {
+        ProofNumber(bits)
     }
 }
 
@@ -36,12 +47,12 @@ impl Ord for ProofNumber {
 
 impl Sum for ProofNumber {
     fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
-        let mut sum = 1;
+        let mut sum: u32 = 1;
 
         for n in iter {
             match n.0 {
                 0 => return ProofNumber(0),
-                m => sum += m - 1,
+                m => sum = sum.saturating_add(m - 1),
             }
         }
 
@@ -65,15 +76,62 @@ impl Add for ProofNumber {
         match (self.0, rhs.0) {
             (0, _) => ProofNumber(0),
             (_, 0) => ProofNumber(0),
-            (n, m) => ProofNumber((n - 1) + m),
+            (n, m) => ProofNumber(n.saturating_add(m.saturating_sub(1))),
+        }
+    }
+}
+
+impl Sub for ProofNumber {
+    type Output = Self;
+
+    /// Subtracts a finite number, saturating at zero.
+    /// Infinity minus any finite number remains infinite.
+    fn sub(self, rhs: Self) -> Self {
+        match (self.0, rhs.0) {
+            (0, 0) => panic!("Cannot subtract infinity from infinity"),
+            (0, _) => ProofNumber(0),
+            (_, 0) => panic!("Cannot subtract infinity from a finite number"),
+            (n, m) => ProofNumber(n.saturating_sub(m - 1).max(1)),
         }
     }
 }
 
-#[cfg(tests)]
+#[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn infinite_is_greater_than_finite() {
         assert!(ProofNumber::infinite() > ProofNumber::finite(1));
     }
+
+    #[test]
+    fn addition_saturates_below_infinity() {
+        let largest = ProofNumber::finite(u32::MAX);
+
+        assert_eq!(largest + ProofNumber::finite(1), largest);
+        assert_eq!(
+            vec![largest, ProofNumber::finite(3)]
+                .into_iter()
+                .sum::<ProofNumber>(),
+            largest
+        );
+        assert!(largest + largest < ProofNumber::infinite());
+    }
+
+    #[test]
+    fn subtraction_saturates() {
+        assert_eq!(
+            ProofNumber::finite(5) - ProofNumber::finite(2),
+            ProofNumber::finite(3)
+        );
+        assert_eq!(
+            ProofNumber::finite(2) - ProofNumber::finite(5),
+            ProofNumber::finite(0)
+        );
+        assert_eq!(
+            ProofNumber::infinite() - ProofNumber::finite(5),
+            ProofNumber::infinite()
+        );
+    }
 }
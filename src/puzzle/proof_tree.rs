@@ -0,0 +1,351 @@
+use super::{terminal_detection, Goal, NoProfile, Profiler, Puzzle, Solver};
+use crate::go::{BoardPosition, GoGame, GoPlayer, Move};
+use petgraph::stable_graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use sgf_parser::{GameNode, GameTree, SgfToken};
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+/// The minimal tree of moves demonstrating the result of a solved puzzle.
+///
+/// Where the winning player is to move, a single winning move is given.
+/// Where the losing player is to move, every legal reply is given.
+#[derive(Debug)]
+pub struct ProofTree {
+    pub game: GoGame,
+    pub variations: Vec<(Move, ProofTree)>,
+}
+
+/// Solving a pruned subtree again, from the same path of positions,
+/// gave a different winner from the one found by the search.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofTreeMismatch {
+    /// The position at the root of the pruned subtree.
+    pub game: GoGame,
+    /// The winner found by the search.
+    pub expected_winner: GoPlayer,
+}
+
+impl Display for ProofTreeMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "solving a pruned subtree again didn't find a win for {}:\n{}",
+            self.expected_winner, self.game.board
+        )
+    }
+}
+
+impl Error for ProofTreeMismatch {}
+
+/// The SGF property of the root node holding the attacker.
+pub(super) const ATTACKER_PROPERTY: &str = "AT";
+/// The SGF property of the root node holding whether seki counts as life.
+pub(super) const SEKI_IS_LIFE_PROPERTY: &str = "SK";
+/// The SGF property of the root node holding the winner.
+pub(super) const WINNER_PROPERTY: &str = "WN";
+/// The SGF property of the root node marking the targets.
+pub(super) const TARGET_PROPERTY: &str = "MA";
+
+pub(super) fn player_to_sgf(player: GoPlayer) -> &'static str {
+    match player {
+        GoPlayer::Black => "B",
+        GoPlayer::White => "W",
+    }
+}
+
+pub(super) fn point_to_sgf(position: BoardPosition) -> String {
+    let (x, y) = position.to_pair();
+
+    [x, y].iter().map(|&i| (b'a' + i) as char).collect()
+}
+
+impl ProofTree {
+    /// Serialises the tree as an SGF game, with a variation for each branch.
+    ///
+    /// The root node records the `goal` and the `winner` the tree proves a
+    /// win for, both in a game comment and in properties read back by
+    /// [`ProofTree::from_sgf`]: `AT` and `WN` hold the attacker and the winner,
+    /// `SK` whether seki counts as life, and `MA` marks the targets.
+    pub fn to_sgf(&self, winner: GoPlayer, goal: Goal) -> String {
+        let mut root = self.game.board.to_sgf_node();
+
+        let seki = if goal.seki_is_life { "life" } else { "death" };
+        root.tokens.push(SgfToken::Unknown((
+            "GC".to_owned(),
+            format!(
+                "The attacker is {}, seki counts as {} and {} wins",
+                goal.attacker, seki, winner
+            ),
+        )));
+
+        for (property, value) in [
+            (ATTACKER_PROPERTY, player_to_sgf(goal.attacker)),
+            (
+                SEKI_IS_LIFE_PROPERTY,
+                if goal.seki_is_life { "1" } else { "0" },
+            ),
+            (WINNER_PROPERTY, player_to_sgf(winner)),
+        ]
+        .iter()
+        {
+            root.tokens.push(SgfToken::Unknown((
+                (*property).to_owned(),
+                (*value).to_owned(),
+            )));
+        }
+
+        for position in goal.targets.positions() {
+            root.tokens.push(SgfToken::Unknown((
+                TARGET_PROPERTY.to_owned(),
+                point_to_sgf(position),
+            )));
+        }
+
+        self.to_game_tree(root).into()
+    }
+
+    fn to_game_tree(&self, first_node: GameNode) -> GameTree {
+        let mut nodes = vec![first_node];
+        let mut current = self;
+
+        while let [(go_move, child)] = current.variations.as_slice() {
+            nodes.push(go_move.to_sgf_node(current.game.current_player));
+            current = child;
+        }
+
+        let variations = current
+            .variations
+            .iter()
+            .map(|(go_move, child)| {
+                child.to_game_tree(go_move.to_sgf_node(current.game.current_player))
+            })
+            .collect();
+
+        GameTree { nodes, variations }
+    }
+}
+
+impl<P: Profiler> Puzzle<P> {
+    /// Extracts the proof tree of a solved puzzle.
+    ///
+    /// Subtrees that were pruned during the search are solved again, from
+    /// the same path of positions, so that the proof tree is complete.
+    /// Solving one again can still give a different winner, such as when the
+    /// search was stopped by a node limit and the subtree can't be solved
+    /// within it, which is returned as an error.
+    pub fn proof_tree(&self) -> Result<ProofTree, ProofTreeMismatch> {
+        assert!(
+            self.is_solved(),
+            "Cannot extract the proof of an unsolved puzzle"
+        );
+
+        let mut path = self.previous_games.clone();
+        path.push(self.game_stack[0]);
+
+        self.proof_tree_from(self.root_id, &mut path)
+    }
+
+    /// Extracts the proof tree from the last position in `path`, which holds
+    /// every position before it too so that repetitions are judged the same
+    /// way as they were during the search.
+    fn proof_tree_from(
+        &self,
+        node_id: NodeIndex,
+        path: &mut Vec<GoGame>,
+    ) -> Result<ProofTree, ProofTreeMismatch> {
+        let game = *path.last().unwrap();
+        let previous_games = &path[..path.len() - 1];
+
+        let winner = if self.tree[node_id].is_proved() {
+            self.player
+        } else {
+            self.player.flip()
+        };
+
+        let mut edges = self.tree.edges(node_id).peekable();
+
+        if edges.peek().is_none() {
+            if node_id != self.root_id
+                && (previous_games.contains(&game)
+                    || terminal_detection::is_terminal(game, self.goal.attacker, self.goal.targets)
+                        .is_some())
+            {
+                return Ok(ProofTree {
+                    game,
+                    variations: Vec::new(),
+                });
+            }
+
+            let mut puzzle = Puzzle::<NoProfile>::with_goal(game, self.goal);
+            puzzle.node_limit = self.node_limit;
+            puzzle.prunes_irrelevant_moves = self.prunes_irrelevant_moves;
+            puzzle.leaf_evaluator = Arc::clone(&self.leaf_evaluator);
+            puzzle.previous_games = previous_games.to_vec();
+            puzzle.solve();
+
+            let sub_winner = if puzzle.is_proved() {
+                game.current_player
+            } else {
+                game.current_player.flip()
+            };
+
+            // The subtree is solved again separately from the rest of the tree,
+            // so make sure that didn't change its result.
+            if sub_winner != winner {
+                return Err(ProofTreeMismatch {
+                    game,
+                    expected_winner: winner,
+                });
+            }
+
+            return puzzle.proof_tree();
+        }
+
+        let is_won_by_winner = |child_id: NodeIndex| {
+            if winner == self.player {
+                self.tree[child_id].is_proved()
+            } else {
+                self.tree[child_id].is_disproved()
+            }
+        };
+
+        let variations = if game.current_player == winner {
+            let edge = edges.find(|edge| is_won_by_winner(edge.target())).unwrap();
+
+            vec![edge]
+        } else {
+            edges.collect()
+        }
+        .into_iter()
+        .map(|edge| {
+            path.push(game.play_move(*edge.weight()).unwrap());
+            let proof_tree = self.proof_tree_from(edge.target(), path);
+            path.pop();
+
+            Ok((*edge.weight(), proof_tree?))
+        })
+        .collect::<Result<_, _>>()?;
+
+        Ok(ProofTree { game, variations })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::go::GoPlayer;
+    use crate::puzzle::AndOrNode;
+    use insta::assert_snapshot;
+
+    fn assert_is_complete(proof_tree: &ProofTree, winner: GoPlayer) {
+        if proof_tree.variations.is_empty() {
+            return;
+        }
+
+        if proof_tree.game.current_player == winner {
+            assert_eq!(proof_tree.variations.len(), 1);
+        } else {
+            assert_eq!(
+                proof_tree.variations.len(),
+                proof_tree.game.generate_moves().len()
+            );
+        }
+
+        for (go_move, child) in proof_tree.variations.iter() {
+            assert_eq!(proof_tree.game.play_move(*go_move), Ok(child.game));
+
+            assert_is_complete(child, winner);
+        }
+    }
+
+    #[test]
+    fn true_ultrasimple2() {
+        let mut puzzle = Puzzle::<NoProfile>::from_sgf(include_str!(
+            "../test_sgfs/puzzles/true_ultrasimple2.sgf"
+        ));
+
+        puzzle.solve();
+
+        assert_snapshot!(puzzle
+            .proof_tree()
+            .unwrap()
+            .to_sgf(GoPlayer::Black, puzzle.goal()));
+    }
+
+    #[test]
+    fn contains_every_defence() {
+        let mut puzzle =
+            Puzzle::<NoProfile>::from_sgf(include_str!("../test_sgfs/puzzles/true_simple1.sgf"));
+
+        puzzle.solve();
+
+        let proof_tree = puzzle.proof_tree().unwrap();
+
+        assert_is_complete(&proof_tree, GoPlayer::Black);
+        assert_eq!(
+            Some(proof_tree.variations[0].0),
+            puzzle.first_move(),
+            "The proof starts with the first move"
+        );
+    }
+
+    #[test]
+    fn complete_with_ko_from_both_sides() {
+        let game = GoGame::from_sgf(include_str!("../test_sgfs/puzzles/ko_simple1.sgf"));
+
+        for &player in GoPlayer::both() {
+            let mut puzzle = Puzzle::<NoProfile>::new(GoGame::from_board(game.board, player));
+
+            puzzle.solve();
+
+            let winner = if puzzle.is_proved() {
+                player
+            } else {
+                player.flip()
+            };
+
+            assert_is_complete(&puzzle.proof_tree().unwrap(), winner);
+        }
+    }
+
+    #[test]
+    fn sgf_has_variations() {
+        let mut puzzle =
+            Puzzle::<NoProfile>::from_sgf(include_str!("../test_sgfs/puzzles/true_simple3.sgf"));
+
+        puzzle.solve();
+
+        let sgf = sgf_parser::parse(
+            &puzzle
+                .proof_tree()
+                .unwrap()
+                .to_sgf(GoPlayer::Black, puzzle.goal()),
+        )
+        .unwrap();
+
+        assert!(sgf.count_variations() > 1);
+    }
+
+    #[test]
+    fn reports_a_pruned_subtree_with_a_different_winner() {
+        let mut puzzle =
+            Puzzle::<NoProfile>::from_sgf(include_str!("../test_sgfs/puzzles/true_simple1.sgf"));
+
+        puzzle.solve();
+
+        // Throw away the search and claim the opposite result at the root,
+        // so it's solved again and found to be a win for the other player.
+        let children: Vec<_> = puzzle.tree.neighbors(puzzle.root_id).collect();
+        for child_id in children {
+            puzzle.tree.remove_node(child_id);
+        }
+        puzzle.tree[puzzle.root_id] = AndOrNode::create_terminal(false);
+
+        let error = puzzle.proof_tree().unwrap_err();
+
+        assert_eq!(error.expected_winner, GoPlayer::White);
+    }
+}
@@ -0,0 +1,497 @@
+//! Describes a life and death problem, checking it for contradictions
+//! before it's turned into a game and goal for the search engines.
+
+use super::Goal;
+use crate::go::{BitBoard, BoardPosition, ExternalLibertiesError, GoBoard, GoGame, GoPlayer};
+use sgf_parser::SgfToken;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// Why a puzzle spec doesn't describe a puzzle.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PuzzleSpecError {
+    InvalidSgf(String),
+    /// Both players have a stone on this point.
+    OverlappingStones(BoardPosition),
+    /// A stone is outside the playable region.
+    StoneOutsideRegion(BoardPosition),
+    /// A block is marked as assumed alive or as having external
+    /// liberties, but there's no stone on this point.
+    MarkNotOnStone(BoardPosition),
+    /// A block has no liberties, so would already have been captured.
+    DeadStones,
+    /// The block with a stone on this point is given more external
+    /// liberties than it touches points outside the region.
+    TooFewExternalLiberties(BoardPosition),
+    /// The attacker wasn't given, and can't be guessed because both
+    /// players, or neither, have stones touching the outside of the region.
+    AmbiguousAttacker,
+    /// The defender has no stones to save.
+    NoDefenderStones,
+    /// A target isn't on one of the defender's stones.
+    TargetNotDefenderStone(BoardPosition),
+}
+
+impl Display for PuzzleSpecError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PuzzleSpecError::InvalidSgf(message) => write!(f, "invalid SGF: {}", message),
+            PuzzleSpecError::OverlappingStones(position) => {
+                write!(f, "both players have a stone on {}", position)
+            }
+            PuzzleSpecError::StoneOutsideRegion(position) => {
+                write!(f, "the stone on {} is outside the region", position)
+            }
+            PuzzleSpecError::MarkNotOnStone(position) => {
+                write!(f, "the block marked on {} has no stone", position)
+            }
+            PuzzleSpecError::DeadStones => write!(f, "a block has no liberties"),
+            PuzzleSpecError::TooFewExternalLiberties(position) => write!(
+                f,
+                "the block on {} doesn't touch enough points outside the region \
+                 for its external liberties",
+                position
+            ),
+            PuzzleSpecError::AmbiguousAttacker => {
+                write!(f, "the attacker can't be guessed, so has to be given")
+            }
+            PuzzleSpecError::NoDefenderStones => write!(f, "the defender has no stones"),
+            PuzzleSpecError::TargetNotDefenderStone(position) => {
+                write!(f, "the target on {} isn't a defender's stone", position)
+            }
+        }
+    }
+}
+
+impl Error for PuzzleSpecError {}
+
+/// A life and death problem: the stones, the region they're played out in,
+/// the player to move first and what each player is trying to achieve.
+///
+/// ```rust
+/// use tsumego_solver::go::{BitBoard, BoardPosition, GoPlayer};
+/// use tsumego_solver::puzzle::{PuzzleSpec, PuzzleSpecError};
+///
+/// let spec = PuzzleSpec::new()
+///     .with_stones(GoPlayer::Black, BitBoard::singleton(BoardPosition::new(0, 0)))
+///     .with_attacker(GoPlayer::Black);
+///
+/// assert_eq!(spec.build(), Err(PuzzleSpecError::NoDefenderStones));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PuzzleSpec {
+    black: BitBoard,
+    white: BitBoard,
+    region: BitBoard,
+    assumed_alive: BitBoard,
+    external_liberty_blocks: BitBoard,
+    external_liberty_stones: BitBoard,
+    external_liberties: usize,
+    first_player: GoPlayer,
+    attacker: Option<GoPlayer>,
+    seki_is_life: bool,
+    targets: BitBoard,
+}
+
+impl Default for PuzzleSpec {
+    fn default() -> PuzzleSpec {
+        PuzzleSpec::new()
+    }
+}
+
+impl PuzzleSpec {
+    /// A spec with no stones, played out over the whole board with Black
+    /// moving first, and the attacker guessed when it's built.
+    pub fn new() -> PuzzleSpec {
+        PuzzleSpec {
+            black: BitBoard::empty(),
+            white: BitBoard::empty(),
+            region: !BitBoard::empty(),
+            assumed_alive: BitBoard::empty(),
+            external_liberty_blocks: BitBoard::empty(),
+            external_liberty_stones: BitBoard::empty(),
+            external_liberties: 0,
+            first_player: GoPlayer::Black,
+            attacker: None,
+            seki_is_life: false,
+            targets: BitBoard::empty(),
+        }
+    }
+
+    /// A spec with the stones of `board`, played out in everything
+    /// but its out of bounds area.
+    pub fn from_board(board: GoBoard) -> PuzzleSpec {
+        PuzzleSpec::new()
+            .with_stones(
+                GoPlayer::Black,
+                board.get_bitboard_for_player(GoPlayer::Black),
+            )
+            .with_stones(
+                GoPlayer::White,
+                board.get_bitboard_for_player(GoPlayer::White),
+            )
+            .with_region(!board.out_of_bounds())
+            .with_assumed_alive(board.assumed_alive())
+            .with_external_liberty_blocks(board.external_liberty_blocks())
+    }
+
+    /// Reads a spec from the first node of an SGF, in the form written by
+    /// [`GoBoard::to_sgf`], with the player to move first taken from its
+    /// `PL` property if there is one.
+    pub fn from_sgf(sgf_string: &str) -> Result<PuzzleSpec, PuzzleSpecError> {
+        let invalid = |message: &str| PuzzleSpecError::InvalidSgf(message.to_owned());
+
+        let sgf = sgf_parser::parse(sgf_string).map_err(|error| invalid(&error.to_string()))?;
+
+        let node = match sgf.nodes.as_slice() {
+            [node] if sgf.variations.is_empty() => node,
+            [] => return Err(invalid("the tree has no nodes")),
+            _ => return Err(invalid("a puzzle can't have moves")),
+        };
+
+        let mut spec = PuzzleSpec::from_board(GoBoard::from_sgf_node(node));
+
+        for token in node.tokens.iter() {
+            match token {
+                SgfToken::Move { .. } => return Err(invalid("a puzzle can't have moves")),
+                SgfToken::Unknown((identifier, value)) if identifier == "PL" => {
+                    spec.first_player = match value.as_str() {
+                        "B" => GoPlayer::Black,
+                        "W" => GoPlayer::White,
+                        _ => return Err(invalid(&format!("unknown player {:?}", value))),
+                    };
+                }
+                _ => {}
+            }
+        }
+
+        Ok(spec)
+    }
+
+    /// Sets the stones of `player`, replacing any they already had.
+    pub fn with_stones(mut self, player: GoPlayer, stones: BitBoard) -> PuzzleSpec {
+        match player {
+            GoPlayer::Black => self.black = stones,
+            GoPlayer::White => self.white = stones,
+        }
+
+        self
+    }
+
+    /// Sets the points the puzzle is played out in, with every other point
+    /// out of bounds. Stones touching the outside of the region can't be
+    /// captured, unless their blocks are given external liberties.
+    pub fn with_region(mut self, region: BitBoard) -> PuzzleSpec {
+        self.region = region;
+        self
+    }
+
+    /// Assumes the blocks with a stone on one of `stones` are alive,
+    /// see [`GoBoard::set_assumed_alive`].
+    pub fn with_assumed_alive(mut self, stones: BitBoard) -> PuzzleSpec {
+        self.assumed_alive = stones;
+        self
+    }
+
+    /// Cuts the blocks with a stone on one of `stones` off from the outside
+    /// of the region, so that they only have the liberties inside it.
+    /// Their external liberties are the points of the region outside the
+    /// area of the problem, see [`GoBoard::set_external_liberties`].
+    pub fn with_external_liberty_blocks(mut self, stones: BitBoard) -> PuzzleSpec {
+        self.external_liberty_blocks = stones;
+        self
+    }
+
+    /// Gives the blocks with a stone on one of `stones` `liberties` external
+    /// liberties each, taken from the points outside the region next to them,
+    /// see [`GoBoard::set_external_liberties`].
+    pub fn with_external_liberties(mut self, stones: BitBoard, liberties: usize) -> PuzzleSpec {
+        self.external_liberty_stones = stones;
+        self.external_liberties = liberties;
+        self
+    }
+
+    pub fn with_first_player(mut self, first_player: GoPlayer) -> PuzzleSpec {
+        self.first_player = first_player;
+        self
+    }
+
+    /// Sets the player trying to kill, instead of guessing them from which
+    /// player has stones touching the outside of the region.
+    pub fn with_attacker(mut self, attacker: GoPlayer) -> PuzzleSpec {
+        self.attacker = Some(attacker);
+        self
+    }
+
+    /// Sets the player trying to live, see [`with_attacker`](PuzzleSpec::with_attacker).
+    pub fn with_defender(self, defender: GoPlayer) -> PuzzleSpec {
+        self.with_attacker(defender.flip())
+    }
+
+    pub fn with_seki_is_life(mut self, seki_is_life: bool) -> PuzzleSpec {
+        self.seki_is_life = seki_is_life;
+        self
+    }
+
+    /// Makes the goal saving, or capturing, the defender's blocks with a stone
+    /// on one of `points`, see [`Goal::with_targets`]. Unlike there, every
+    /// point has to be on a defender's stone.
+    pub fn with_targets(mut self, points: BitBoard) -> PuzzleSpec {
+        self.targets = points;
+        self
+    }
+
+    pub fn stones(&self, player: GoPlayer) -> BitBoard {
+        match player {
+            GoPlayer::Black => self.black,
+            GoPlayer::White => self.white,
+        }
+    }
+
+    pub fn region(&self) -> BitBoard {
+        self.region
+    }
+
+    pub fn first_player(&self) -> GoPlayer {
+        self.first_player
+    }
+
+    /// The attacker, if it's been given rather than left to be guessed.
+    pub fn attacker(&self) -> Option<GoPlayer> {
+        self.attacker
+    }
+
+    pub fn targets(&self) -> BitBoard {
+        self.targets
+    }
+
+    /// The board set up by the spec, without checking it, and leaving out
+    /// any points that both players have a stone on, and the external
+    /// liberties if they can't all be given.
+    pub fn board(&self) -> GoBoard {
+        self.board_with_external_liberties()
+            .unwrap_or_else(|_| self.board_without_external_liberties())
+    }
+
+    fn board_without_external_liberties(&self) -> GoBoard {
+        let mut board = GoBoard::new(
+            self.black & !self.white,
+            self.white & !self.black,
+            !self.region,
+        );
+        board.set_assumed_alive(self.assumed_alive);
+        board.set_external_liberty_blocks(self.external_liberty_blocks);
+
+        board
+    }
+
+    fn board_with_external_liberties(&self) -> Result<GoBoard, PuzzleSpecError> {
+        let mut board = self.board_without_external_liberties();
+        let mut given = BitBoard::empty();
+
+        for position in self.external_liberty_stones.positions() {
+            if !(given & BitBoard::singleton(position)).is_empty() {
+                continue;
+            }
+
+            board
+                .set_external_liberties(position, self.external_liberties)
+                .map_err(|error| match error {
+                    ExternalLibertiesError::NoStone => PuzzleSpecError::MarkNotOnStone(position),
+                    ExternalLibertiesError::TooFewOutOfBoundsPoints(_) => {
+                        PuzzleSpecError::TooFewExternalLiberties(position)
+                    }
+                })?;
+
+            let stones = GoPlayer::both()
+                .map(|&player| board.get_bitboard_for_player(player))
+                .find(|stones| !(*stones & BitBoard::singleton(position)).is_empty())
+                .unwrap_or_else(BitBoard::empty);
+            given = given | BitBoard::singleton(position).flood_fill(stones);
+        }
+
+        Ok(board)
+    }
+
+    /// Checks the spec, and turns it into the starting position and the goal
+    /// that the search engines take.
+    pub fn build(self) -> Result<(GoGame, Goal), PuzzleSpecError> {
+        let stones = self.black | self.white;
+
+        if let Some(position) = (self.black & self.white).positions().next() {
+            return Err(PuzzleSpecError::OverlappingStones(position));
+        }
+
+        if let Some(position) = (stones & !self.region).positions().next() {
+            return Err(PuzzleSpecError::StoneOutsideRegion(position));
+        }
+
+        let marks =
+            self.assumed_alive | self.external_liberty_blocks | self.external_liberty_stones;
+
+        if let Some(position) = (marks & !stones).positions().next() {
+            return Err(PuzzleSpecError::MarkNotOnStone(position));
+        }
+
+        let board = self.board_with_external_liberties()?;
+
+        if board.has_dead_groups() {
+            return Err(PuzzleSpecError::DeadStones);
+        }
+
+        let attacker = match self.attacker {
+            Some(attacker) => attacker,
+            None => self.guess_attacker()?,
+        };
+
+        let defender_stones = board.get_bitboard_for_player(attacker.flip());
+
+        if defender_stones.is_empty() {
+            return Err(PuzzleSpecError::NoDefenderStones);
+        }
+
+        if let Some(position) = (self.targets & !defender_stones).positions().next() {
+            return Err(PuzzleSpecError::TargetNotDefenderStone(position));
+        }
+
+        let goal = Goal::new(attacker)
+            .with_seki_is_life(self.seki_is_life)
+            .with_targets(board, self.targets);
+
+        Ok((GoGame::from_board(board, self.first_player), goal))
+    }
+
+    /// The only player with stones touching the outside of the region.
+    fn guess_attacker(&self) -> Result<GoPlayer, PuzzleSpecError> {
+        let outside = (!self.region).expand_one();
+
+        let mut touching = GoPlayer::both()
+            .copied()
+            .filter(|&player| !(outside & self.stones(player)).is_empty());
+
+        match (touching.next(), touching.next()) {
+            (Some(attacker), None) => Ok(attacker),
+            _ => Err(PuzzleSpecError::AmbiguousAttacker),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_sgf() {
+        let sgf = include_str!("../test_sgfs/puzzles/true_simple1.sgf");
+        let game = GoGame::from_sgf(sgf);
+
+        let (built_game, goal) = PuzzleSpec::from_sgf(sgf).unwrap().build().unwrap();
+
+        assert_eq!(built_game.board, game.board);
+        assert_eq!(built_game.current_player, GoPlayer::White);
+        assert_eq!(goal, Goal::guess(game));
+    }
+
+    #[test]
+    fn rejects_moves_in_sgf() {
+        assert_eq!(
+            PuzzleSpec::from_sgf(include_str!("../test_sgfs/ko_rule_simple.sgf")),
+            Err(PuzzleSpecError::InvalidSgf(
+                "a puzzle can't have moves".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn attacker_is_given_when_both_players_touch_the_outside() {
+        let board = GoGame::from_sgf(include_str!("../test_sgfs/walled_group.sgf")).board;
+        let spec = PuzzleSpec::from_board(board);
+
+        assert_eq!(spec.build().unwrap().1.attacker, GoPlayer::White);
+
+        let outside_stone = BoardPosition::new(0, 5);
+        let spec = spec
+            .with_region(spec.region().set(outside_stone))
+            .with_stones(
+                GoPlayer::Black,
+                board
+                    .get_bitboard_for_player(GoPlayer::Black)
+                    .set(outside_stone),
+            );
+
+        assert_eq!(spec.build(), Err(PuzzleSpecError::AmbiguousAttacker));
+
+        let (_, goal) = spec.with_attacker(GoPlayer::Black).build().unwrap();
+
+        assert_eq!(goal.attacker, GoPlayer::Black);
+    }
+
+    #[test]
+    fn external_liberties_are_taken_from_outside_the_region() {
+        let board = GoGame::from_sgf(include_str!("../test_sgfs/walled_group.sgf")).board;
+        let spec = PuzzleSpec::from_board(board);
+        let wall = BitBoard::singleton(BoardPosition::new(2, 0));
+
+        let (game, _) = spec.with_external_liberties(wall, 1).build().unwrap();
+
+        assert_eq!(
+            (board.out_of_bounds() & !game.board.out_of_bounds()).count(),
+            1
+        );
+        assert_eq!(
+            spec.with_external_liberties(wall, 100).build(),
+            Err(PuzzleSpecError::TooFewExternalLiberties(
+                BoardPosition::new(2, 0)
+            ))
+        );
+        assert_eq!(spec.with_external_liberties(wall, 100).board(), board);
+    }
+
+    #[test]
+    fn rejects_contradictions() {
+        let board = GoGame::from_sgf(include_str!("../test_sgfs/walled_group.sgf")).board;
+        let spec = PuzzleSpec::from_board(board);
+        let black_stone = BoardPosition::new(0, 0);
+        let white_stone = BoardPosition::new(2, 0);
+        let empty_point = BoardPosition::new(0, 1);
+
+        assert!(spec.build().is_ok());
+        assert_eq!(
+            spec.with_stones(
+                GoPlayer::White,
+                board
+                    .get_bitboard_for_player(GoPlayer::White)
+                    .set(black_stone)
+            )
+            .build(),
+            Err(PuzzleSpecError::OverlappingStones(black_stone))
+        );
+        assert_eq!(
+            spec.with_region(spec.region() & !BitBoard::singleton(white_stone))
+                .build(),
+            Err(PuzzleSpecError::StoneOutsideRegion(white_stone))
+        );
+        assert_eq!(
+            spec.with_assumed_alive(BitBoard::singleton(empty_point))
+                .build(),
+            Err(PuzzleSpecError::MarkNotOnStone(empty_point))
+        );
+        assert_eq!(
+            spec.with_stones(
+                GoPlayer::Black,
+                board
+                    .get_bitboard_for_player(GoPlayer::Black)
+                    .set(empty_point)
+            )
+            .build(),
+            Err(PuzzleSpecError::DeadStones)
+        );
+        assert_eq!(
+            spec.with_defender(GoPlayer::White)
+                .with_targets(BitBoard::singleton(black_stone))
+                .build(),
+            Err(PuzzleSpecError::TargetNotDefenderStone(black_stone))
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! Limits the moves searched to those that can change the result.
+//!
+//! The attacker's stones touching the out of bounds area or assumed to be
+//! alive are safe, as are any attacker blocks that Benson's algorithm proves
+//! to be unconditionally alive, so the result only depends on whether the defender
+//! can make a living group. An empty region that only borders safe attacker
+//! stones and the out of bounds area is walled off from the rest of the puzzle,
+//! and if it is too small for the defender to make two eyes in, no move inside
+//! it can matter:
+//!
+//! * A defender stone played there can never connect to the defender's other
+//!   stones or take away a liberty from anything but safe attacker stones, and
+//!   a group built up from such stones can't live.
+//! * An attacker stone played there only takes away liberties from the
+//!   attacker's own safe stones, and can't reach the defender's stones.
+//!
+//! Either way, such a move leaves the life of the defender exactly as a pass
+//! would. With targets, the defender doesn't need a living group at all, only
+//! to save the targets, so a region is walled off whatever its size, and the
+//! defender's blocks that can never be captured, such as those assumed to be
+//! alive, are part of the wall too. A stone played next to one of them can
+//! only join a block that is already safe.
+//!
+//! The one thing that can tell these moves apart from a pass is the rule used
+//! to end the search, under which the player who passes second loses, as
+//! playing one of them is a way to avoid passing. So that the parity of
+//! passes is kept, the first move outside the zone is kept as a tenuki: a
+//! move that changes nothing but the number of such moves left. Which one is
+//! kept doesn't matter, as after any of them the rest are still walled off,
+//! or if they now border the new stone, are back in the zone and searched in
+//! full.
+//!
+//! Positions where the defender already has unconditionally alive blocks
+//! are terminal, so those blocks never need to be considered here.
+
+use super::terminal_detection::safe_attacker_stones;
+use crate::go::{BitBoard, GoBoard, GoGame, GoPlayer, Move};
+use std::mem;
+
+/// The points where a move may change the result.
+pub fn relevance_zone(board: GoBoard, attacker: GoPlayer, targets: BitBoard) -> BitBoard {
+    let mut safe_stones = safe_attacker_stones(board, attacker)
+        | board.unconditionally_alive_blocks_for_player(attacker);
+
+    if !targets.is_empty() {
+        safe_stones = safe_stones | board.safe_blocks_for_player(attacker.flip());
+    }
+
+    board
+        .in_bounds_empty_cells()
+        .groups()
+        .filter(|&region| {
+            let borders_other_stones =
+                !(region.immediate_exterior() & !safe_stones & !board.out_of_bounds()).is_empty();
+
+            borders_other_stones || (targets.is_empty() && has_room_for_two_eyes(region))
+        })
+        .fold(BitBoard::empty(), |zone, region| zone | region)
+}
+
+/// Whether a group could live in `region`, which needs two non-adjacent
+/// interior points or more than two interior points.
+fn has_room_for_two_eyes(region: BitBoard) -> bool {
+    let interior = region.interior();
+    let interior_count = interior.count();
+
+    interior_count > 2 || (interior_count == 2 && !interior.singletons().is_empty())
+}
+
+/// Removes the moves outside the relevance zone, keeping passes
+/// and the first of the moves outside it as a tenuki.
+pub fn prune_moves(
+    game: GoGame,
+    attacker: GoPlayer,
+    targets: BitBoard,
+    moves: &mut Vec<(GoGame, Move)>,
+) {
+    let zone = relevance_zone(game.board, attacker, targets);
+    let mut has_tenuki = false;
+
+    moves.retain(|(_, go_move)| match go_move {
+        Move::Pass => true,
+        Move::Place(position) if zone.is_set(*position) => true,
+        Move::Place(_) => !mem::replace(&mut has_tenuki, true),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::go::BoardPosition;
+    use crate::puzzle::{Goal, NoProfile, Puzzle, SolveLimits, SolveOutcome, Solver};
+
+    #[test]
+    fn excludes_walled_off_points() {
+        let game = GoGame::from_sgf(include_str!("../test_sgfs/puzzles/true_simple1.sgf"));
+
+        let zone = relevance_zone(game.board, GoPlayer::White, BitBoard::empty());
+
+        assert!(!zone.is_set(BoardPosition::new(0, 0)));
+        assert!(!zone.is_set(BoardPosition::new(0, 2)));
+        assert!(zone.is_set(BoardPosition::new(3, 0)));
+        assert!(zone.is_set(BoardPosition::new(3, 1)));
+    }
+
+    #[test]
+    fn keeps_one_tenuki() {
+        let game = GoGame::from_sgf(include_str!("../test_sgfs/puzzles/true_simple1.sgf"));
+        let game = GoGame::from_board(game.board, GoPlayer::White);
+        let zone = relevance_zone(game.board, GoPlayer::White, BitBoard::empty());
+
+        let mut moves = game.generate_moves();
+        let move_count = moves.len();
+        prune_moves(game, GoPlayer::White, BitBoard::empty(), &mut moves);
+
+        let tenukis: Vec<_> = moves
+            .iter()
+            .filter(|(_, go_move)| match go_move {
+                Move::Pass => false,
+                Move::Place(position) => !zone.is_set(*position),
+            })
+            .collect();
+
+        assert_eq!(tenukis.len(), 1);
+        assert!(moves.len() < move_count);
+    }
+
+    #[test]
+    fn pruning_agrees_on_every_puzzle() {
+        let sgfs = [
+            include_str!("../test_sgfs/puzzles/ko_repetition1.sgf"),
+            include_str!("../test_sgfs/puzzles/ko_simple1.sgf"),
+            include_str!("../test_sgfs/puzzles/target_capture1.sgf"),
+            include_str!("../test_sgfs/puzzles/target_save1.sgf"),
+            include_str!("../test_sgfs/puzzles/true_complex1.sgf"),
+            include_str!("../test_sgfs/puzzles/true_complex2.sgf"),
+            include_str!("../test_sgfs/puzzles/true_complex3.sgf"),
+            include_str!("../test_sgfs/puzzles/true_complex4.sgf"),
+            include_str!("../test_sgfs/puzzles/true_medium1.sgf"),
+            include_str!("../test_sgfs/puzzles/true_simple1.sgf"),
+            include_str!("../test_sgfs/puzzles/true_simple2.sgf"),
+            include_str!("../test_sgfs/puzzles/true_simple3.sgf"),
+            include_str!("../test_sgfs/puzzles/true_simple4.sgf"),
+            include_str!("../test_sgfs/puzzles/true_ultrasimple1.sgf"),
+            include_str!("../test_sgfs/puzzles/true_ultrasimple2.sgf"),
+        ];
+
+        // The larger puzzles can't be solved in a reasonable time from both
+        // sides, so those are only compared when both searches finish.
+        let limits = SolveLimits::default().with_max_expanded_nodes(5_000);
+
+        for sgf in sgfs.iter() {
+            let game = GoGame::from_sgf(sgf);
+
+            for &player in GoPlayer::both() {
+                let game = GoGame::from_board(game.board, player);
+                let goal = Goal::guess(game);
+                let all_defender_stones = game.board.get_bitboard_for_player(goal.attacker.flip());
+
+                for &goal in [goal, goal.with_targets(game.board, all_defender_stones)].iter() {
+                    let outcome = |prunes_irrelevant_moves| {
+                        let mut puzzle = Puzzle::<NoProfile>::with_goal(game, goal);
+                        puzzle.set_relevance_zone_pruning(prunes_irrelevant_moves);
+                        puzzle.solve_with_limits(limits)
+                    };
+
+                    let (unpruned, pruned) = (outcome(false), outcome(true));
+
+                    assert!(
+                        pruned == unpruned
+                            || pruned == SolveOutcome::NodeLimit
+                            || unpruned == SolveOutcome::NodeLimit,
+                        "{:?} without pruning but {:?} with it\n{}",
+                        unpruned,
+                        pruned,
+                        game.board
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+/// Bounds on how much work a single solve may do.
+///
+/// Only the wall time depends on the machine, so solves limited by the
+/// other two give the same result everywhere.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SolveLimits {
+    /// The longest the search may run for.
+    pub timeout: Option<Duration>,
+    /// The most nodes the search may expand.
+    pub max_expanded_nodes: Option<usize>,
+    /// The most nodes the tree may hold at once.
+    pub max_tree_size: Option<usize>,
+}
+
+impl SolveLimits {
+    pub fn with_timeout(mut self, timeout: Duration) -> SolveLimits {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_max_expanded_nodes(mut self, max_expanded_nodes: usize) -> SolveLimits {
+        self.max_expanded_nodes = Some(max_expanded_nodes);
+        self
+    }
+
+    pub fn with_max_tree_size(mut self, max_tree_size: usize) -> SolveLimits {
+        self.max_tree_size = Some(max_tree_size);
+        self
+    }
+}
+
+/// Why a solve stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SolveOutcome {
+    /// The player to move wins.
+    Proved,
+    /// The player to move loses.
+    Disproved,
+    /// The search ran for longer than the timeout.
+    TimedOut,
+    /// The search expanded as many nodes as it was allowed to.
+    NodeLimit,
+    /// The tree grew larger than it was allowed to.
+    MemoryLimit,
+}
+
+impl SolveOutcome {
+    pub fn is_solved(self) -> bool {
+        match self {
+            SolveOutcome::Proved | SolveOutcome::Disproved => true,
+            SolveOutcome::TimedOut | SolveOutcome::NodeLimit | SolveOutcome::MemoryLimit => false,
+        }
+    }
+}
@@ -0,0 +1,294 @@
+//! A common interface to the search engines, so that callers can switch between them.
+
+use super::solve_limits::{SolveLimits, SolveOutcome};
+use super::{guess_attacker, AlphaBeta, Dfpn, LambdaSearch, ParallelDfpn, Profiler, Puzzle};
+use crate::go::{BitBoard, GoBoard, GoGame, GoPlayer, Move};
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// What each player is trying to achieve.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Goal {
+    /// The player trying to kill, with the other trying to live.
+    pub attacker: GoPlayer,
+    /// Whether the defender reaching a seki counts as a win for the defender.
+    pub seki_is_life: bool,
+    /// The defender's stones that have to be saved, with the defender's
+    /// other stones free to live or die. If there are none, the defender
+    /// has to make any of their blocks live.
+    pub targets: BitBoard,
+}
+
+impl Goal {
+    /// The goal of `attacker` killing the defender, who has to live unconditionally.
+    pub fn new(attacker: GoPlayer) -> Goal {
+        Goal {
+            attacker,
+            seki_is_life: false,
+            targets: BitBoard::empty(),
+        }
+    }
+
+    /// The goal of killing the defender, guessing the attacker from
+    /// which player has stones touching the out of bounds area.
+    pub fn guess(game: GoGame) -> Goal {
+        Goal::new(guess_attacker(game))
+    }
+
+    pub fn with_seki_is_life(mut self, seki_is_life: bool) -> Goal {
+        self.seki_is_life = seki_is_life;
+        self
+    }
+
+    /// Makes the goal saving, or capturing, the defender's blocks that have
+    /// a stone on one of `points` in `board`. Points without a defender's
+    /// stone are ignored, so marks on the attacker's stones do nothing.
+    pub fn with_targets(mut self, board: GoBoard, points: BitBoard) -> Goal {
+        let defender_stones = board.get_bitboard_for_player(self.attacker.flip());
+
+        self.targets = points.flood_fill(defender_stones);
+        self
+    }
+}
+
+/// How much work a search did.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SolveStats {
+    pub expanded_nodes: usize,
+    pub elapsed: Duration,
+}
+
+/// The result of a search.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SolveResult {
+    pub outcome: SolveOutcome,
+    /// Moves known to win for the player to move. Most engines stop at
+    /// the first winning move they find, so this isn't always all of them.
+    pub winning_moves: Vec<Move>,
+    pub stats: SolveStats,
+}
+
+/// A search engine for life and death problems.
+pub trait Solver {
+    /// Creates a solver for `game`, with the player to move moving first.
+    fn with_goal(game: GoGame, goal: Goal) -> Self
+    where
+        Self: Sized;
+
+    /// Searches until the puzzle is solved or one of the limits is reached.
+    ///
+    /// Searching again after a limit is reached carries on from where
+    /// the search stopped, as far as the engine is able to.
+    fn search(&mut self, limits: SolveLimits) -> SolveResult;
+}
+
+/// The search engines that can be chosen between at runtime.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Engine {
+    /// Proof-number search, keeping the whole tree in memory, using [`Puzzle`].
+    ProofNumber,
+    /// Depth-first proof-number search, using [`Dfpn`].
+    Dfpn,
+    /// Depth-first proof-number search on every core, using [`ParallelDfpn`].
+    ParallelDfpn,
+    /// Lambda search, using [`LambdaSearch`].
+    Lambda,
+    /// Exhaustive alpha-beta search, using [`AlphaBeta`].
+    AlphaBeta,
+}
+
+impl Engine {
+    pub fn all() -> impl Iterator<Item = &'static Engine> {
+        [
+            Engine::ProofNumber,
+            Engine::Dfpn,
+            Engine::ParallelDfpn,
+            Engine::Lambda,
+            Engine::AlphaBeta,
+        ]
+        .iter()
+    }
+
+    /// The name the engine is chosen by.
+    pub fn name(self) -> &'static str {
+        match self {
+            Engine::ProofNumber => "pns",
+            Engine::Dfpn => "dfpn",
+            Engine::ParallelDfpn => "parallel-dfpn",
+            Engine::Lambda => "lambda",
+            Engine::AlphaBeta => "alpha-beta",
+        }
+    }
+
+    pub fn solver<P: Profiler>(self, game: GoGame, goal: Goal) -> Box<dyn Solver> {
+        match self {
+            Engine::ProofNumber => Box::new(Puzzle::<P>::with_goal(game, goal)),
+            Engine::Dfpn => Box::new(Dfpn::<P>::with_goal(game, goal)),
+            Engine::ParallelDfpn => Box::new(ParallelDfpn::<P>::with_goal(game, goal)),
+            Engine::Lambda => Box::new(LambdaSearch::<P>::with_goal(game, goal)),
+            Engine::AlphaBeta => Box::new(AlphaBeta::<P>::with_goal(game, goal)),
+        }
+    }
+}
+
+impl Display for Engine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownEngine(String);
+
+impl Display for UnknownEngine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = Engine::all().map(|engine| engine.name()).collect();
+
+        write!(
+            f,
+            "unknown engine {:?}, expected one of {}",
+            self.0,
+            names.join(", ")
+        )
+    }
+}
+
+impl Error for UnknownEngine {}
+
+impl FromStr for Engine {
+    type Err = UnknownEngine;
+
+    fn from_str(name: &str) -> Result<Engine, UnknownEngine> {
+        Engine::all()
+            .copied()
+            .find(|engine| engine.name() == name)
+            .ok_or_else(|| UnknownEngine(name.to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::go::BoardPosition;
+    use crate::puzzle::NoProfile;
+
+    #[test]
+    fn engines_agree() {
+        let game = GoGame::from_sgf(include_str!("../test_sgfs/puzzles/true_ultrasimple1.sgf"));
+
+        for &engine in Engine::all() {
+            let mut solver = engine.solver::<NoProfile>(game, Goal::guess(game));
+
+            let result = solver.search(SolveLimits::default());
+
+            assert_eq!(result.outcome, SolveOutcome::Proved, "{}", engine);
+            assert!(!result.winning_moves.is_empty(), "{}", engine);
+            assert!(result.stats.expanded_nodes > 0, "{}", engine);
+        }
+    }
+
+    #[test]
+    fn engines_agree_from_both_sides() {
+        let sgfs = [
+            include_str!("../test_sgfs/puzzles/true_ultrasimple1.sgf"),
+            include_str!("../test_sgfs/puzzles/true_ultrasimple2.sgf"),
+            include_str!("../test_sgfs/puzzles/true_simple1.sgf"),
+            include_str!("../test_sgfs/puzzles/true_simple3.sgf"),
+            include_str!("../test_sgfs/puzzles/ko_simple1.sgf"),
+        ];
+
+        for sgf in sgfs.iter() {
+            let game = GoGame::from_sgf(sgf);
+
+            for &player in GoPlayer::both() {
+                let game = GoGame::from_board(game.board, player);
+                let goal = Goal::guess(game);
+
+                let expected = Engine::ProofNumber
+                    .solver::<NoProfile>(game, goal)
+                    .search(SolveLimits::default())
+                    .outcome;
+
+                for &engine in Engine::all() {
+                    // Alpha-beta search is exhaustive, so it can only finish
+                    // the smallest of these in a reasonable time.
+                    let limits = match engine {
+                        Engine::AlphaBeta => SolveLimits::default().with_max_expanded_nodes(20_000),
+                        _ => SolveLimits::default(),
+                    };
+
+                    let outcome = engine
+                        .solver::<NoProfile>(game, goal)
+                        .search(limits)
+                        .outcome;
+
+                    assert!(
+                        outcome == expected || outcome == SolveOutcome::NodeLimit,
+                        "{} gave {:?} instead of {:?}\n{}",
+                        engine,
+                        outcome,
+                        expected,
+                        game.board
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn target_goals() {
+        let game = GoGame::from_board(
+            GoGame::from_sgf(include_str!("../test_sgfs/puzzles/target_capture1.sgf")).board,
+            GoPlayer::White,
+        );
+        let target = BoardPosition::new(6, 0);
+        let capture = Move::Place(BoardPosition::new(6, 1));
+
+        for &engine in Engine::all() {
+            let goal = Goal::guess(game);
+
+            let result = engine
+                .solver::<NoProfile>(game, goal)
+                .search(SolveLimits::default());
+            assert_eq!(result.outcome, SolveOutcome::Disproved, "{}", engine);
+
+            let goal = goal.with_targets(game.board, BitBoard::singleton(target));
+
+            let result = engine
+                .solver::<NoProfile>(game, goal)
+                .search(SolveLimits::default());
+            assert_eq!(result.outcome, SolveOutcome::Proved, "{}", engine);
+            assert!(result.winning_moves.contains(&capture), "{}", engine);
+        }
+    }
+
+    #[test]
+    fn targets_are_whole_defender_blocks() {
+        let game = GoGame::from_sgf(include_str!("../test_sgfs/puzzles/true_ultrasimple1.sgf"));
+        let goal = Goal::new(GoPlayer::White);
+
+        let marks = BitBoard::singleton(BoardPosition::new(0, 1))
+            .set(BoardPosition::new(0, 2))
+            .set(BoardPosition::new(0, 0));
+
+        assert_eq!(
+            goal.with_targets(game.board, marks).targets,
+            game.board.get_bitboard_for_player(GoPlayer::Black)
+        );
+    }
+
+    #[test]
+    fn parse_engine() {
+        for &engine in Engine::all() {
+            assert_eq!(engine.name().parse(), Ok(engine));
+        }
+
+        assert_eq!(
+            "minimax".parse::<Engine>(),
+            Err(UnknownEngine("minimax".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use super::outcome::Outcome;
+use super::{guess_attacker, Goal, NoProfile, Puzzle, Solver};
+use crate::go::{GoBoard, GoGame, GoPlayer, Move};
+
+/// The standard life and death status of a position,
+/// taking into account both players moving first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    /// The defender lives whoever moves first.
+    Alive,
+    /// The defender dies whoever moves first.
+    Dead,
+    /// Whoever moves first wins.
+    Unsettled,
+    /// The result depends on a ko for at least one player moving first.
+    Ko,
+    /// The defender lives in seki whoever moves first.
+    Seki,
+}
+
+/// The status of a position, along with which player is the attacker and
+/// the moves that decide the result.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Classification {
+    pub status: Status,
+    pub attacker: GoPlayer,
+    /// The attacker's first move when moving first makes a difference.
+    pub attacker_key_move: Option<Move>,
+    /// The defender's first move when moving first makes a difference.
+    pub defender_key_move: Option<Move>,
+}
+
+impl Classification {
+    /// Classifies the board, guessing the attacker from which player
+    /// has stones touching the out of bounds area.
+    pub fn classify(board: GoBoard) -> Classification {
+        Self::classify_with_attacker(
+            board,
+            guess_attacker(GoGame::from_board(board, GoPlayer::Black)),
+        )
+    }
+
+    /// Solves the board with each player to move, then compares the outcomes.
+    pub fn classify_with_attacker(board: GoBoard, attacker: GoPlayer) -> Classification {
+        let defender = attacker.flip();
+
+        let attacker_first =
+            Outcome::solve_with_attacker(GoGame::from_board(board, attacker), attacker);
+        let defender_first =
+            Outcome::solve_with_attacker(GoGame::from_board(board, defender), attacker);
+
+        let status = match (defender_first, attacker_first) {
+            (Outcome::Alive, Outcome::Alive) => Status::Alive,
+            (Outcome::Dead, Outcome::Dead) => Status::Dead,
+            (Outcome::Seki, Outcome::Seki) => Status::Seki,
+            (Outcome::Ko(_), _) | (_, Outcome::Ko(_)) => Status::Ko,
+            _ => Status::Unsettled,
+        };
+
+        let (attacker_key_move, defender_key_move) = match status {
+            Status::Unsettled | Status::Ko => (
+                key_move(board, attacker, attacker),
+                key_move(board, defender, attacker),
+            ),
+            Status::Alive | Status::Dead | Status::Seki => (None, None),
+        };
+
+        Classification {
+            status,
+            attacker,
+            attacker_key_move,
+            defender_key_move,
+        }
+    }
+}
+
+/// The first move that gets `player` their best result when moving first,
+/// with kos won by `player` and a seki counted against them.
+fn key_move(board: GoBoard, player: GoPlayer, attacker: GoPlayer) -> Option<Move> {
+    let game = GoGame::from_board(board, player).with_ko_master(Some(player));
+
+    let goal = Goal::new(attacker).with_seki_is_life(player == attacker);
+    let mut puzzle = Puzzle::<NoProfile>::with_goal(game, goal);
+    puzzle.solve();
+
+    puzzle.first_move()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::go::BoardPosition;
+
+    #[test]
+    fn unsettled() {
+        let board = GoGame::from_sgf(include_str!("../test_sgfs/puzzles/true_simple1.sgf")).board;
+
+        assert_eq!(
+            Classification::classify(board),
+            Classification {
+                status: Status::Unsettled,
+                attacker: GoPlayer::White,
+                attacker_key_move: Some(Move::Place(BoardPosition::new(4, 0))),
+                defender_key_move: Some(Move::Place(BoardPosition::new(4, 0))),
+            }
+        );
+    }
+
+    #[test]
+    fn seki() {
+        let board = GoGame::from_sgf(include_str!("../test_sgfs/life_and_death/seki.sgf")).board;
+
+        assert_eq!(
+            Classification::classify(board),
+            Classification {
+                status: Status::Seki,
+                attacker: GoPlayer::Black,
+                attacker_key_move: None,
+                defender_key_move: None,
+            }
+        );
+    }
+
+    #[test]
+    fn ko() {
+        let board = GoGame::from_sgf(include_str!("../test_sgfs/life_and_death/ko.sgf")).board;
+
+        assert_eq!(Classification::classify(board).status, Status::Ko);
+    }
+}
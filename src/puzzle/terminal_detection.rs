@@ -1,36 +1,106 @@
-use crate::go::{GoBoard, GoGame, GoPlayer, PassState};
+use crate::go::{BitBoard, GoBoard, GoGame, GoPlayer, PassState};
 
-pub fn is_terminal(game: GoGame, player: GoPlayer, attacker: GoPlayer) -> Option<bool> {
+/// The life and death status of the defender in a terminal position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TerminalStatus {
+    /// The defender has won, usually by living unconditionally.
+    DefenderLives,
+    /// Neither player can capture the other's stones, so the
+    /// defender survives without living unconditionally.
+    Seki,
+    /// The attacker has won, usually by leaving no room for two eyes.
+    DefenderDies,
+}
+
+impl TerminalStatus {
+    /// The player who wins with this status.
+    ///
+    /// A seki counts as a win for the defender only if `seki_is_life` is set.
+    pub fn winner(self, attacker: GoPlayer, seki_is_life: bool) -> GoPlayer {
+        let defender_wins = match self {
+            TerminalStatus::DefenderLives => true,
+            TerminalStatus::Seki => seki_is_life,
+            TerminalStatus::DefenderDies => false,
+        };
+
+        if defender_wins {
+            attacker.flip()
+        } else {
+            attacker
+        }
+    }
+}
+
+/// Whether the position is terminal, and if so the status of the defender.
+///
+/// With no `targets`, the defender wins by making any of their blocks live.
+/// Otherwise the defender has to save every one of the `targets`, and loses
+/// as soon as any of them is captured, whatever happens to their other stones.
+pub fn is_terminal(game: GoGame, attacker: GoPlayer, targets: BitBoard) -> Option<TerminalStatus> {
     let defender = attacker.flip();
 
+    // If a target has been captured, the attacker wins
+    if !(targets & !game.board.get_bitboard_for_player(defender)).is_empty() {
+        Some(TerminalStatus::DefenderDies)
+    // If the defender has unconditionally alive blocks, the defender wins
+    } else if is_saved(
+        game.board.unconditionally_alive_blocks_for_player(defender),
+        targets,
+    ) {
+        Some(TerminalStatus::DefenderLives)
+    // If neither player can safely play, the defender is alive in seki
+    } else if is_seki(game.board, attacker, targets) {
+        Some(TerminalStatus::Seki)
     // If both players pass sequentially, the game ends and
     // the player to pass second loses.
-    if game.pass_state == PassState::PassedTwice {
-        Some(game.current_player == player)
-    // If the defender has unconditionally alive blocks, the defender wins
-    } else if !game
-        .board
-        .unconditionally_alive_blocks_for_player(defender)
-        .is_empty()
-    {
-        Some(defender == player)
-    // If the defender doesn't have any space to create eyes, the attacker wins
+    } else if game.pass_state == PassState::PassedTwice {
+        if game.current_player == defender {
+            Some(TerminalStatus::DefenderLives)
+        } else {
+            Some(TerminalStatus::DefenderDies)
+        }
+    // If the defender can become unconditionally alive whatever the attacker
+    // plays, the defender wins.
+    } else if is_safe_under_alternating_play(game, defender, targets) {
+        Some(TerminalStatus::DefenderLives)
+    // If the defender doesn't have any space to create eyes, the attacker wins.
     } else if !can_defender_live(game.board, attacker) {
-        Some(attacker == player)
+        Some(TerminalStatus::DefenderDies)
     // Otherwise, the result is a non-terminal node
     } else {
         None
     }
 }
 
+/// Whether the defender's `stones` that survive save the `targets`,
+/// or save any block if there are no targets.
+fn is_saved(stones: BitBoard, targets: BitBoard) -> bool {
+    if targets.is_empty() {
+        !stones.is_empty()
+    } else {
+        (targets & !stones).is_empty()
+    }
+}
+
+fn is_safe_under_alternating_play(game: GoGame, defender: GoPlayer, targets: BitBoard) -> bool {
+    if targets.is_empty() {
+        game.is_safe_under_alternating_play(defender)
+    } else {
+        game.are_stones_safe_under_alternating_play(defender, targets)
+    }
+}
+
+/// The attacker's stones that can never be captured, see
+/// [`GoBoard::safe_blocks_for_player`].
+pub(super) fn safe_attacker_stones(board: GoBoard, attacker: GoPlayer) -> BitBoard {
+    board.safe_blocks_for_player(attacker)
+}
+
 /// Whether it's possible for the defender to live.
 /// It's possible if there are at least two-non-adjacent interior points
 /// in the area not occupied by safe stones.
 fn can_defender_live(board: GoBoard, attacker: GoPlayer) -> bool {
-    let safe_attacker_stones = board
-        .out_of_bounds()
-        .expand_one()
-        .flood_fill(board.get_bitboard_for_player(attacker));
+    let safe_attacker_stones = safe_attacker_stones(board, attacker);
 
     let maximum_living_shape = !safe_attacker_stones & !board.out_of_bounds();
 
@@ -39,3 +109,139 @@ fn can_defender_live(board: GoBoard, attacker: GoPlayer) -> bool {
 
     interior_count > 2 || (interior_count == 2 && !interior.singletons().is_empty())
 }
+
+/// Whether the defender is alive in seki, with nowhere
+/// left for either player to play.
+///
+/// A point only counts as unplayable if playing there is suicide or a
+/// self-atari that captures nothing, see [`GoBoard::is_unplayable`]. Such
+/// a self-atari can still be the right move, for example a sacrifice that
+/// reduces the defender to one eye once it's captured, or one that starts a
+/// ko, so this can call a position seki even though the attacker could kill.
+fn is_seki(board: GoBoard, attacker: GoPlayer, targets: BitBoard) -> bool {
+    let empty_points = board.empty_cells() & !board.out_of_bounds();
+    let defender = attacker.flip();
+
+    empty_points
+        .positions()
+        .all(|position| board.is_unplayable(position))
+        && is_saved(
+            board.seki_blocks_for_player(defender)
+                | board.unconditionally_alive_blocks_for_player(defender),
+            targets,
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::go::{BoardPosition, Move};
+
+    #[test]
+    fn seki() {
+        let game = GoGame::from_sgf(include_str!("../test_sgfs/life_and_death/seki.sgf"));
+
+        assert_eq!(
+            is_terminal(game, GoPlayer::Black, BitBoard::empty()),
+            Some(TerminalStatus::Seki)
+        );
+    }
+
+    #[test]
+    fn seki_with_space_left_to_play() {
+        let mut board =
+            GoGame::from_sgf(include_str!("../test_sgfs/life_and_death/seki.sgf")).board;
+        let open_area = (8..11)
+            .flat_map(|column| (0..5).map(move |row| BoardPosition::new(column, row)))
+            .fold(BitBoard::empty(), BitBoard::set);
+        board.set_out_of_bounds(board.out_of_bounds() & !open_area);
+
+        assert!(!board.seki_blocks_for_player(GoPlayer::White).is_empty());
+        assert_eq!(
+            is_terminal(
+                GoGame::from_board(board, GoPlayer::Black),
+                GoPlayer::Black,
+                BitBoard::empty()
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn player_to_pass_second_loses() {
+        let game = GoGame::from_sgf(include_str!("../test_sgfs/puzzles/true_simple1.sgf"));
+
+        assert_eq!(
+            is_terminal(game.pass().pass(), GoPlayer::White, BitBoard::empty()),
+            Some(TerminalStatus::DefenderLives)
+        );
+        assert_eq!(
+            is_terminal(
+                GoGame::from_board(game.board, GoPlayer::White)
+                    .pass()
+                    .pass(),
+                GoPlayer::White,
+                BitBoard::empty()
+            ),
+            Some(TerminalStatus::DefenderDies)
+        );
+    }
+
+    #[test]
+    fn walls_with_external_liberties_are_not_safe() {
+        let mut board = GoGame::from_sgf(include_str!("../test_sgfs/walled_group.sgf")).board;
+        let wall = board.get_bitboard_for_player(GoPlayer::White);
+
+        assert_eq!(safe_attacker_stones(board, GoPlayer::White), wall);
+
+        board
+            .set_external_liberties(BoardPosition::new(2, 0), 1)
+            .unwrap();
+
+        assert!(safe_attacker_stones(board, GoPlayer::White).is_empty());
+
+        board.set_assumed_alive(wall);
+
+        assert_eq!(safe_attacker_stones(board, GoPlayer::White), wall);
+    }
+
+    #[test]
+    fn assumed_alive_defenders_live() {
+        let mut board = GoGame::from_sgf(include_str!("../test_sgfs/walled_group.sgf")).board;
+        let game = GoGame::from_board(board, GoPlayer::Black);
+
+        assert_eq!(
+            is_terminal(game, GoPlayer::White, BitBoard::empty()),
+            Some(TerminalStatus::DefenderDies)
+        );
+
+        board.set_assumed_alive(BitBoard::singleton(BoardPosition::new(0, 0)));
+        let game = GoGame::from_board(board, GoPlayer::Black);
+
+        assert_eq!(
+            is_terminal(game, GoPlayer::White, BitBoard::empty()),
+            Some(TerminalStatus::DefenderLives)
+        );
+    }
+
+    #[test]
+    fn capturing_a_target_wins_for_the_attacker() {
+        let game = GoGame::from_sgf(include_str!("../test_sgfs/puzzles/target_capture1.sgf"));
+        let target = BitBoard::singleton(BoardPosition::new(6, 0));
+
+        assert_eq!(
+            is_terminal(game, GoPlayer::White, BitBoard::empty()),
+            Some(TerminalStatus::DefenderLives)
+        );
+        assert_eq!(is_terminal(game, GoPlayer::White, target), None);
+
+        let game = GoGame::from_board(game.board, GoPlayer::White)
+            .play_move(Move::Place(BoardPosition::new(6, 1)))
+            .unwrap();
+
+        assert_eq!(
+            is_terminal(game, GoPlayer::White, target),
+            Some(TerminalStatus::DefenderDies)
+        );
+    }
+}